@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+
+use crate::global_types::{Activatable, Carrier, IsPlayer};
+use crate::laser::TriggerLaserShot;
+use crate::loading::GameAssets;
+use crate::utils::some_or;
+use crate::world_snapshot::Retired;
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<AnimationClips>();
+        app.init_asset_loader::<AnimationClipsLoader>();
+        app.add_startup_system(setup_sprite_animations);
+        app.add_system(set_player_animation_state);
+        app.add_system(set_part_animation_state);
+        app.add_system(
+            animate_sprites
+                .after(set_player_animation_state)
+                .after(set_part_animation_state),
+        );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize)]
+pub enum AnimationState {
+    Idle,
+    Carry,
+    SpinUp,
+    Active,
+    Firing,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Deserialize)]
+pub enum LoopMode {
+    #[default]
+    Loop,
+    /// Play once and stay on the last frame.
+    Once,
+}
+
+#[derive(Deserialize)]
+pub struct AnimationClip {
+    pub frames: Vec<usize>,
+    #[serde(default = "default_fps")]
+    pub fps: f32,
+    #[serde(default)]
+    pub loop_mode: LoopMode,
+}
+
+fn default_fps() -> f32 {
+    1.0
+}
+
+impl AnimationClip {
+    fn duration(&self) -> f32 {
+        self.frames.len() as f32 / self.fps
+    }
+
+    fn frame_at(&self, time: f32) -> usize {
+        let frame = (time * self.fps) as usize;
+        let frame = match self.loop_mode {
+            LoopMode::Loop => frame % self.frames.len(),
+            LoopMode::Once => frame.min(self.frames.len() - 1),
+        };
+        self.frames[frame]
+    }
+}
+
+/// The clips of a texture atlas, keyed by state. Loaded from a `.anim.json` file placed next to
+/// the atlas' image.
+#[derive(TypeUuid, Deserialize)]
+#[uuid = "c3b0e1f4-6d2a-4f8e-a5d7-9e14b2c8f031"]
+pub struct AnimationClips(HashMap<AnimationState, AnimationClip>);
+
+#[derive(Default)]
+pub struct AnimationClipsLoader;
+
+impl AssetLoader for AnimationClipsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let clips: AnimationClips = serde_json::from_slice(bytes)?;
+            for (state, clip) in clips.0.iter() {
+                if clip.frames.is_empty() || clip.fps <= 0.0 {
+                    anyhow::bail!("Clip {:?} must have frames and a positive fps", state);
+                }
+            }
+            load_context.set_default_asset(LoadedAsset::new(clips));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["anim.json"]
+    }
+}
+
+/// The clips of each texture atlas. Sprites whose atlas (or current state) has no clip keep
+/// whatever frame they have.
+pub struct SpriteAnimations(HashMap<Handle<TextureAtlas>, Handle<AnimationClips>>);
+
+fn setup_sprite_animations(game_assets: Res<GameAssets>, mut commands: Commands) {
+    commands.insert_resource(SpriteAnimations(
+        [
+            (&game_assets.player, &game_assets.player_animations),
+            (&game_assets.hover, &game_assets.hover_animations),
+            (&game_assets.laser, &game_assets.laser_animations),
+            (&game_assets.stationary, &game_assets.stationary_animations),
+            (&game_assets.rotator, &game_assets.rotator_animations),
+        ]
+        .into_iter()
+        .map(|(atlas, clips)| (atlas.clone(), clips.clone()))
+        .collect(),
+    ));
+}
+
+impl SpriteAnimations {
+    fn clip<'a>(
+        &self,
+        clips_assets: &'a Assets<AnimationClips>,
+        atlas: &Handle<TextureAtlas>,
+        state: AnimationState,
+    ) -> Option<&'a AnimationClip> {
+        clips_assets.get(self.0.get(atlas)?)?.0.get(&state)
+    }
+}
+
+#[derive(Component)]
+pub struct SpriteAnimation {
+    state: AnimationState,
+    /// Time since the current state started.
+    time: f32,
+}
+
+impl SpriteAnimation {
+    pub fn new(state: AnimationState) -> Self {
+        Self { state, time: 0.0 }
+    }
+
+    fn set_state(&mut self, state: AnimationState) {
+        if self.state != state {
+            self.state = state;
+            self.time = 0.0;
+        }
+    }
+}
+
+/// The player's sheet only has an empty-handed frame and a carrying frame, so those are the only
+/// states it has.
+fn set_player_animation_state(mut query: Query<(&mut SpriteAnimation, &Carrier), With<IsPlayer>>) {
+    for (mut animation, carrier) in query.iter_mut() {
+        let state = if carrier.carrying.is_some() {
+            AnimationState::Carry
+        } else {
+            AnimationState::Idle
+        };
+        animation.set_state(state);
+    }
+}
+
+fn set_part_animation_state(
+    mut query: Query<(&mut SpriteAnimation, &Activatable, &Handle<TextureAtlas>), Without<Retired>>,
+    mut laser_shot_reader: EventReader<TriggerLaserShot>,
+    sprite_animations: Res<SpriteAnimations>,
+    clips_assets: Res<Assets<AnimationClips>>,
+) {
+    for event in laser_shot_reader.iter() {
+        if let Ok((mut animation, _, _)) = query.get_mut(event.ignore_entity) {
+            animation.set_state(AnimationState::Firing);
+        }
+    }
+    for (mut animation, activatable, atlas) in query.iter_mut() {
+        if !activatable.active {
+            animation.set_state(AnimationState::Idle);
+            continue;
+        }
+        let finished = sprite_animations
+            .clip(&clips_assets, atlas, animation.state)
+            .map_or(true, |clip| clip.duration() <= animation.time);
+        match animation.state {
+            AnimationState::Idle => animation.set_state(AnimationState::SpinUp),
+            AnimationState::SpinUp | AnimationState::Firing if finished => {
+                animation.set_state(AnimationState::Active);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn animate_sprites(
+    time: Res<Time>,
+    sprite_animations: Res<SpriteAnimations>,
+    clips_assets: Res<Assets<AnimationClips>>,
+    mut query: Query<(
+        &mut SpriteAnimation,
+        &Handle<TextureAtlas>,
+        &mut TextureAtlasSprite,
+    )>,
+) {
+    for (mut animation, atlas, mut sprite) in query.iter_mut() {
+        animation.time += time.delta_seconds();
+        let clip =
+            some_or!(sprite_animations.clip(&clips_assets, atlas, animation.state); continue);
+        let frame = clip.frame_at(animation.time);
+        if sprite.index != frame {
+            sprite.index = frame;
+        }
+    }
+}
@@ -1,10 +1,11 @@
 use bevy::prelude::*;
 use bevy::text::Text2dSize;
-use bevy_egui::EguiSettings;
+use bevy_egui::{EguiContext, EguiSettings, EguiSystem};
 use bevy_yoleck::{YoleckEditorState, YoleckExtForApp, YoleckPopulate, YoleckTypeHandler};
 use serde::{Deserialize, Serialize};
 
-use crate::global_types::CameraInclude;
+use crate::camera_zone::{ActiveCameraZone, CameraZoneFraming};
+use crate::global_types::{AppState, CameraInclude, IsPlayer};
 use crate::utils::some_or;
 
 pub struct CameraPlugin {
@@ -13,7 +14,28 @@ pub struct CameraPlugin {
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
+        app.init_resource::<LevelBounds>();
+        app.init_resource::<CameraFocus>();
+        app.init_resource::<CameraZoneTransition>();
+        app.init_resource::<CameraSafeArea>();
+        app.insert_resource(CameraFitSettings { padding: 0.05 });
+        app.insert_resource(CameraFollowSettings {
+            view_height: 12.0,
+            deadzone: Vec2::new(1.5, 1.0),
+            smoothing: 5.0,
+        });
+        app.add_system_set(SystemSet::on_enter(AppState::LoadLevel).with_system(
+            |mut camera_focus: ResMut<CameraFocus>,
+             mut camera_zone_transition: ResMut<CameraZoneTransition>| {
+                camera_focus.0 = None;
+                *camera_zone_transition = Default::default();
+            },
+        ));
         app.add_startup_system(setup_camera);
+        app.add_system_to_stage(
+            CoreStage::PostUpdate,
+            update_camera_safe_area.before(EguiSystem::ProcessOutput),
+        );
         app.add_system_set(
             SystemSet::on_update(YoleckEditorState::GameActive)
                 .with_system(update_camera_transform),
@@ -43,16 +65,134 @@ fn setup_camera(mut commands: Commands) {
     commands.spawn_bundle(camera);
 }
 
-#[allow(clippy::type_complexity)]
+/// The bounding box of all the [`CameraInclude`] entities, as `[left, bottom, right, top]`.
+#[derive(Default)]
+pub struct LevelBounds(pub Option<[f32; 4]>);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum CameraMode {
+    /// Fit the entire level in the view.
+    #[default]
+    FitLevel,
+    /// Follow the player at a fixed zoom. If the level has [`CameraMarker`]s, the view is kept
+    /// inside their bounding box instead of inside the whole level.
+    FollowPlayer,
+}
+
+pub struct CameraFollowSettings {
+    /// How many world units are visible vertically.
+    pub view_height: f32,
+    /// Half-size of the rectangle around the view's center in which the player can move without
+    /// the camera following.
+    pub deadzone: Vec2,
+    /// Higher values catch up with the player faster.
+    pub smoothing: f32,
+}
+
+pub struct CameraFitSettings {
+    /// Space left around the framed region, as a fraction of the safe area on each side.
+    pub padding: f32,
+}
+
+/// How much of each window edge is covered by UI panels, in logical pixels (the units of the 2D
+/// camera's projection). The camera frames the level inside the rest of the window.
+#[derive(Default, Debug, PartialEq)]
+pub struct CameraSafeArea {
+    pub left: f32,
+    pub bottom: f32,
+    pub right: f32,
+    pub top: f32,
+}
+
+impl CameraSafeArea {
+    /// The size of the uncovered part of the projection, never smaller than a single pixel.
+    fn size(&self, projection: &OrthographicProjection) -> Vec2 {
+        Vec2::new(
+            projection.right - projection.left - self.left - self.right,
+            projection.top - projection.bottom - self.bottom - self.top,
+        )
+        .max(Vec2::ONE)
+    }
+
+    /// Offset of the uncovered part's center from the projection's center.
+    fn center_offset(&self) -> Vec2 {
+        0.5 * Vec2::new(self.left - self.right, self.bottom - self.top)
+    }
+}
+
+/// Panels take their space from egui's available rect, so whatever is left of it is what the
+/// camera can use. Runs after all the UI systems so that it sees every panel shown this frame.
+fn update_camera_safe_area(
+    mut egui_context: ResMut<EguiContext>,
+    egui_settings: Res<EguiSettings>,
+    mut safe_area: ResMut<CameraSafeArea>,
+) {
+    let egui_context = egui_context.ctx_mut();
+    let screen_rect = egui_context.screen_rect();
+    let available_rect = egui_context.available_rect();
+    // A central panel (e.g. a menu) takes everything. It is drawn over the game anyway, so frame
+    // the level as if it was not there.
+    let new_safe_area = if available_rect.is_positive() {
+        let scale_factor = egui_settings.scale_factor as f32;
+        CameraSafeArea {
+            left: (available_rect.left() - screen_rect.left()) * scale_factor,
+            bottom: (screen_rect.bottom() - available_rect.bottom()) * scale_factor,
+            right: (screen_rect.right() - available_rect.right()) * scale_factor,
+            top: (available_rect.top() - screen_rect.top()) * scale_factor,
+        }
+    } else {
+        CameraSafeArea::default()
+    };
+    // Avoid triggering change detection every frame
+    if *safe_area != new_safe_area {
+        *safe_area = new_safe_area;
+    }
+}
+
+/// The point the camera follows. `None` means the camera should snap to the player instead of
+/// smoothly moving there.
+#[derive(Default)]
+pub struct CameraFocus(pub Option<Vec2>);
+
+/// Entering or leaving a camera zone eases the camera into the new view - regardless of the camera
+/// mode, which may otherwise snap.
+#[derive(Default)]
+struct CameraZoneTransition {
+    /// The zone that was framed on the previous frame. `None` before the level's first frame, so
+    /// that the camera snaps into place when the level starts.
+    framed_zone: Option<Option<Entity>>,
+    /// Set when the framed zone changes, and cleared once the camera reaches its new view.
+    in_progress: bool,
+}
+
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
 fn update_camera_transform(
+    time: Res<Time>,
+    mut level_bounds: ResMut<LevelBounds>,
+    mut camera_focus: ResMut<CameraFocus>,
+    mut camera_zone_transition: ResMut<CameraZoneTransition>,
+    camera_follow_settings: Res<CameraFollowSettings>,
+    camera_fit_settings: Res<CameraFitSettings>,
+    safe_area: Res<CameraSafeArea>,
     mut cameras_query: Query<(&mut Transform, &OrthographicProjection), With<Camera>>,
     camera_included_objects_query: Query<
-        (&GlobalTransform, AnyOf<(&Sprite, &Text2dSize)>),
+        (
+            &GlobalTransform,
+            AnyOf<(&Sprite, &Text2dSize)>,
+            Option<&IsCameraMarker>,
+        ),
         With<CameraInclude>,
     >,
+    players_query: Query<&GlobalTransform, With<IsPlayer>>,
+    level_camera_mode_query: Query<&LevelCameraMode>,
+    active_camera_zone: Res<ActiveCameraZone>,
+    camera_zones_query: Query<(&GlobalTransform, &CameraZoneFraming)>,
 ) {
     let mut minmax: Option<[f32; 4]> = None;
-    for (global_transform, (sprite, text_2d_size)) in camera_included_objects_query.iter() {
+    let mut markers_minmax: Option<[f32; 4]> = None;
+    for (global_transform, (sprite, text_2d_size), is_camera_marker) in
+        camera_included_objects_query.iter()
+    {
         let (vec_to_min, vec_to_max) = if let Some(sprite) = sprite {
             let vec = 0.5 * sprite.custom_size.unwrap().extend(0.0);
             (-vec, vec)
@@ -63,32 +203,187 @@ fn update_camera_transform(
         };
         let min_corner = global_transform.mul_vec3(vec_to_min);
         let max_corner = global_transform.mul_vec3(vec_to_max);
-        minmax = if let Some([l, b, r, t]) = minmax {
-            Some([
-                l.min(min_corner.x),
-                b.min(min_corner.y),
-                r.max(max_corner.x),
-                t.max(max_corner.y),
-            ])
-        } else {
-            Some([min_corner.x, min_corner.y, max_corner.x, max_corner.y])
-        };
+        minmax = Some(extend_minmax(minmax, min_corner, max_corner));
+        if is_camera_marker.is_some() {
+            markers_minmax = Some(extend_minmax(markers_minmax, min_corner, max_corner));
+        }
     }
+    level_bounds.0 = minmax;
     let minmax = some_or!(minmax; return);
-    let world_width = minmax[2] - minmax[0];
-    let world_height = minmax[3] - minmax[1];
-    for (mut transform, projection) in cameras_query.iter_mut() {
-        let projection_width = projection.right - projection.left;
-        let projection_height = projection.top - projection.bottom;
-        let width_ratio = world_width / projection_width;
-        let height_ratio = world_height / (projection_height - 50.0);
-        let chosen_ratio = width_ratio.max(height_ratio) * 1.1;
-        transform.scale = Vec3::new(chosen_ratio, chosen_ratio, 1.0);
-        transform.translation.x = 0.5 * (minmax[0] + minmax[2]);
-        transform.translation.y = 0.5 * (minmax[1] + minmax[3]) + 50.0 * chosen_ratio;
+
+    let framed_zone = active_camera_zone
+        .0
+        .filter(|zone| camera_zones_query.contains(*zone));
+    if let Some(previous_framed_zone) = camera_zone_transition.framed_zone.replace(framed_zone) {
+        if previous_framed_zone != framed_zone {
+            camera_zone_transition.in_progress = true;
+        }
+    }
+    let smooth_lerp_factor = 1.0 - (-camera_follow_settings.smoothing * time.delta_seconds()).exp();
+    let transition_lerp_factor = if camera_zone_transition.in_progress {
+        smooth_lerp_factor
+    } else {
+        1.0
+    };
+    let mut reached_target = true;
+
+    if let Some((zone_transform, zone_framing)) =
+        framed_zone.and_then(|zone| camera_zones_query.get(zone).ok())
+    {
+        let rect = zone_framing.framed_rect(zone_transform);
+        // Give the follow mode a focus to start from once the players leave the zone
+        camera_focus.0 = Some(
+            camera_focus
+                .0
+                .unwrap_or(0.5 * Vec2::new(rect[0] + rect[2], rect[1] + rect[3])),
+        );
+        for (mut transform, projection) in cameras_query.iter_mut() {
+            let target = fit_rect(projection, &safe_area, &camera_fit_settings, rect);
+            reached_target &= lerp_camera(&mut transform, target, transition_lerp_factor);
+        }
+        if reached_target {
+            camera_zone_transition.in_progress = false;
+        }
+        return;
+    }
+
+    let camera_mode = level_camera_mode_query
+        .get_single()
+        .map(|level_camera_mode| level_camera_mode.0)
+        .unwrap_or_default();
+    match camera_mode {
+        CameraMode::FitLevel => {
+            camera_focus.0 = None;
+            let mut minmax = minmax;
+            // Players are not part of the level bounds, but they must stay in view even outside
+            // them.
+            for player_transform in players_query.iter() {
+                let position = player_transform.translation();
+                minmax = extend_minmax(
+                    Some(minmax),
+                    position - Vec3::new(0.5, 0.5, 0.0),
+                    position + Vec3::new(0.5, 0.5, 0.0),
+                );
+            }
+            for (mut transform, projection) in cameras_query.iter_mut() {
+                let target = fit_rect(projection, &safe_area, &camera_fit_settings, minmax);
+                reached_target &= lerp_camera(&mut transform, target, transition_lerp_factor);
+            }
+        }
+        CameraMode::FollowPlayer => {
+            let num_players = players_query.iter().count();
+            if num_players == 0 {
+                return;
+            }
+            let players_center = players_query
+                .iter()
+                .map(|transform| transform.translation().truncate())
+                .sum::<Vec2>()
+                / num_players as f32;
+            let deadzone = camera_follow_settings.deadzone;
+            let focus = camera_focus
+                .0
+                .map(|focus| focus.clamp(players_center - deadzone, players_center + deadzone))
+                .unwrap_or(players_center);
+            let is_snapping = camera_focus.0.is_none() && !camera_zone_transition.in_progress;
+            camera_focus.0 = Some(focus);
+            let [left, bottom, right, top] = markers_minmax.unwrap_or(minmax);
+            for (mut transform, projection) in cameras_query.iter_mut() {
+                let safe_size = safe_area.size(projection);
+                let ratio = camera_follow_settings.view_height / safe_size.y;
+                let half_view = 0.5 * ratio * safe_size;
+                let clamp_axis = |value: f32, min: f32, max: f32| {
+                    if max < min {
+                        // The view is larger than the region - center it
+                        0.5 * (min + max)
+                    } else {
+                        value.clamp(min, max)
+                    }
+                };
+                let view_center = Vec2::new(
+                    clamp_axis(focus.x, left + half_view.x, right - half_view.x),
+                    clamp_axis(focus.y, bottom + half_view.y, top - half_view.y),
+                );
+                let target = CameraTarget {
+                    translation: view_center - ratio * safe_area.center_offset(),
+                    ratio,
+                };
+                let lerp_factor = if is_snapping { 1.0 } else { smooth_lerp_factor };
+                reached_target &= lerp_camera(&mut transform, target, lerp_factor);
+                // Only the position is smoothed - the zoom is fixed in this mode, except when
+                // easing out of a zone.
+                if !camera_zone_transition.in_progress {
+                    transform.scale = Vec3::new(ratio, ratio, 1.0);
+                }
+            }
+        }
+    }
+    if reached_target {
+        camera_zone_transition.in_progress = false;
+    }
+}
+
+struct CameraTarget {
+    translation: Vec2,
+    ratio: f32,
+}
+
+/// Where the camera needs to be to show all of `rect` (given as `[left, bottom, right, top]`)
+/// inside the safe area.
+fn fit_rect(
+    projection: &OrthographicProjection,
+    safe_area: &CameraSafeArea,
+    camera_fit_settings: &CameraFitSettings,
+    rect: [f32; 4],
+) -> CameraTarget {
+    let [left, bottom, right, top] = rect;
+    let rect_size = Vec2::new(right - left, top - bottom);
+    let safe_size = safe_area.size(projection);
+    let ratios = rect_size / safe_size;
+    let ratio = ratios.max_element() * (1.0 + 2.0 * camera_fit_settings.padding);
+    let rect_center = 0.5 * Vec2::new(left + right, bottom + top);
+    CameraTarget {
+        translation: rect_center - ratio * safe_area.center_offset(),
+        ratio,
     }
 }
 
+/// Moves the camera `lerp_factor` of the way toward the target. A factor of `1.0` snaps to it.
+///
+/// Returns whether the camera is now (practically) at the target.
+fn lerp_camera(transform: &mut Transform, target: CameraTarget, lerp_factor: f32) -> bool {
+    let ratio = transform.scale.x + (target.ratio - transform.scale.x) * lerp_factor;
+    let translation = transform
+        .translation
+        .truncate()
+        .lerp(target.translation, lerp_factor);
+    transform.scale = Vec3::new(ratio, ratio, 1.0);
+    transform.translation.x = translation.x;
+    transform.translation.y = translation.y;
+    translation.distance(target.translation) < 0.01
+        && (ratio - target.ratio).abs() < 0.001 * target.ratio
+}
+
+fn extend_minmax(minmax: Option<[f32; 4]>, min_corner: Vec3, max_corner: Vec3) -> [f32; 4] {
+    if let Some([l, b, r, t]) = minmax {
+        [
+            l.min(min_corner.x),
+            b.min(min_corner.y),
+            r.max(max_corner.x),
+            t.max(max_corner.y),
+        ]
+    } else {
+        [min_corner.x, min_corner.y, max_corner.x, max_corner.y]
+    }
+}
+
+/// Placed by the level's settings entity. Levels without it use [`CameraMode::FitLevel`].
+#[derive(Component)]
+pub struct LevelCameraMode(pub CameraMode);
+
+#[derive(Component)]
+pub struct IsCameraMarker;
+
 #[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct CameraMarker {
     #[serde(default)]
@@ -98,6 +393,7 @@ pub struct CameraMarker {
 fn populate_camera_marker(mut populate: YoleckPopulate<CameraMarker>) {
     populate.populate(|ctx, _data, mut cmd| {
         cmd.insert(CameraInclude);
+        cmd.insert(IsCameraMarker);
         cmd.insert_bundle(SpriteBundle {
             sprite: Sprite {
                 color: Color::PURPLE.clone().set_a(0.5).to_owned(),
@@ -0,0 +1,147 @@
+use bevy::prelude::*;
+use bevy_yoleck::{
+    egui, YoleckEdit, YoleckEditorState, YoleckExtForApp, YoleckPopulate, YoleckTypeHandler,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::global_types::{AppState, IsPlayer};
+
+pub struct CameraZonePlugin;
+
+impl Plugin for CameraZonePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveCameraZone>();
+        app.add_yoleck_handler({
+            YoleckTypeHandler::<CameraZone>::new("CameraZone")
+                .populate_with(populate)
+                .with(crate::yoleck_utils::position_adapter(
+                    |camera_zone: &mut CameraZone| (&mut camera_zone.position, camera_zone.size),
+                    -0.5,
+                ))
+                .edit_with(edit)
+        });
+        app.add_system_set(SystemSet::on_enter(AppState::LoadLevel).with_system(
+            |mut active_camera_zone: ResMut<ActiveCameraZone>| {
+                active_camera_zone.0 = None;
+            },
+        ));
+        app.add_system_set(
+            SystemSet::on_update(YoleckEditorState::GameActive)
+                .with_system(update_active_camera_zone),
+        );
+    }
+}
+
+/// While the players are inside this region, the camera frames it instead of using the level's
+/// camera mode.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct CameraZone {
+    #[serde(default)]
+    position: Vec2,
+    #[serde(default = "default_size")]
+    size: IVec2,
+    /// Extra space shown around the zone.
+    #[serde(default = "default_margin")]
+    margin: f32,
+}
+
+fn default_size() -> IVec2 {
+    IVec2::new(8, 6)
+}
+
+fn default_margin() -> f32 {
+    0.5
+}
+
+#[derive(Component)]
+pub struct CameraZoneFraming {
+    half_size: Vec2,
+    margin: f32,
+}
+
+impl CameraZoneFraming {
+    fn contains(&self, zone_transform: &GlobalTransform, point: Vec2) -> bool {
+        let offset = point - zone_transform.translation().truncate();
+        offset.abs().cmple(self.half_size).all()
+    }
+
+    /// As `[left, bottom, right, top]`.
+    pub fn framed_rect(&self, zone_transform: &GlobalTransform) -> [f32; 4] {
+        let center = zone_transform.translation().truncate();
+        let half_size = self.half_size + Vec2::splat(self.margin);
+        let [left, bottom] = (center - half_size).to_array();
+        let [right, top] = (center + half_size).to_array();
+        [left, bottom, right, top]
+    }
+}
+
+/// The zone the players are currently in. When zones overlap, the one whose center is nearest to
+/// the players is used.
+#[derive(Default)]
+pub struct ActiveCameraZone(pub Option<Entity>);
+
+fn populate(mut populate: YoleckPopulate<CameraZone>) {
+    populate.populate(|ctx, data, mut cmd| {
+        cmd.insert_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: Color::CYAN.clone().set_a(0.2).to_owned(),
+                custom_size: Some(data.size.as_vec2()),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        cmd.insert(Visibility {
+            is_visible: ctx.is_in_editor(),
+        });
+        cmd.insert(CameraZoneFraming {
+            half_size: 0.5 * data.size.as_vec2(),
+            margin: data.margin,
+        });
+    });
+}
+
+fn edit(mut edit: YoleckEdit<CameraZone>, mut commands: Commands) {
+    edit.edit(|ctx, data, ui| {
+        ui.add(
+            egui::DragValue::new(&mut data.margin)
+                .prefix("Margin:")
+                .clamp_range(0.0..=10.0)
+                .speed(0.05),
+        );
+        crate::yoleck_utils::edit_size(ctx, &mut commands, ui, &mut data.position, &mut data.size);
+    });
+}
+
+fn update_active_camera_zone(
+    players_query: Query<&GlobalTransform, With<IsPlayer>>,
+    zones_query: Query<(Entity, &GlobalTransform, &CameraZoneFraming)>,
+    mut active_camera_zone: ResMut<ActiveCameraZone>,
+) {
+    let num_players = players_query.iter().count();
+    if num_players == 0 {
+        active_camera_zone.0 = None;
+        return;
+    }
+    let players_center = players_query
+        .iter()
+        .map(|transform| transform.translation().truncate())
+        .sum::<Vec2>()
+        / num_players as f32;
+    let nearest_zone = zones_query
+        .iter()
+        .filter(|(_, transform, framing)| framing.contains(transform, players_center))
+        .map(|(entity, transform, _)| {
+            (
+                entity,
+                transform
+                    .translation()
+                    .truncate()
+                    .distance_squared(players_center),
+            )
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| entity);
+    if active_camera_zone.0 != nearest_zone {
+        active_camera_zone.0 = nearest_zone;
+    }
+}
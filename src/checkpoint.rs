@@ -0,0 +1,125 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use bevy_rapier2d::rapier::prelude::CollisionEventFlags;
+use bevy_yoleck::{YoleckExtForApp, YoleckPopulate, YoleckTypeHandler};
+use serde::{Deserialize, Serialize};
+
+use crate::global_types::{AppState, IsPlayer};
+use crate::utils::{entities_ordered_by_type, some_or};
+use crate::world_snapshot::{WorldSnapshot, WorldSnapshotter};
+
+pub struct CheckpointPlugin;
+
+impl Plugin for CheckpointPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CheckpointState>();
+        app.add_event::<RespawnAtCheckpoint>();
+        app.add_yoleck_handler({
+            YoleckTypeHandler::<Checkpoint>::new("Checkpoint")
+                .populate_with(populate)
+                .with(crate::yoleck_utils::position_adapter(
+                    |checkpoint: &mut Checkpoint| (&mut checkpoint.position, IVec2::ONE),
+                    -0.1,
+                ))
+        });
+        app.add_system_set(SystemSet::on_enter(AppState::LoadLevel).with_system(
+            |mut checkpoint_state: ResMut<CheckpointState>| {
+                *checkpoint_state = Default::default();
+            },
+        ));
+        app.add_system_set({
+            SystemSet::on_update(AppState::Game).with_system(handle_touching_checkpoint)
+        });
+        app.add_system(handle_respawn_at_checkpoint);
+        app.add_system(update_checkpoint_sprites);
+    }
+}
+
+#[derive(Default)]
+pub struct CheckpointState {
+    active_checkpoint: Option<Entity>,
+    snapshot: Option<WorldSnapshot>,
+}
+
+impl CheckpointState {
+    pub fn has_snapshot(&self) -> bool {
+        self.snapshot.is_some()
+    }
+}
+
+pub struct RespawnAtCheckpoint;
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    #[serde(default)]
+    position: Vec2,
+}
+
+#[derive(Component)]
+pub struct IsCheckpoint;
+
+fn populate(mut populate: YoleckPopulate<Checkpoint>) {
+    populate.populate(|_ctx, _data, mut cmd| {
+        cmd.insert_bundle(SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(0.25, 1.0)),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        cmd.insert(RigidBody::Fixed);
+        cmd.insert(Collider::cuboid(0.5, 0.5));
+        cmd.insert(Sensor);
+        cmd.insert(ActiveEvents::COLLISION_EVENTS);
+        cmd.insert(IsCheckpoint);
+    });
+}
+
+fn handle_touching_checkpoint(
+    mut reader: EventReader<CollisionEvent>,
+    player_query: Query<(), With<IsPlayer>>,
+    checkpoint_query: Query<(), With<IsCheckpoint>>,
+    mut checkpoint_state: ResMut<CheckpointState>,
+    world_snapshotter: WorldSnapshotter,
+) {
+    for event in reader.iter() {
+        if let &CollisionEvent::Started(e1, e2, CollisionEventFlags::SENSOR) = event {
+            let [_player_entity, checkpoint_entity] = some_or!(
+                entities_ordered_by_type!([e1, e2], player_query, checkpoint_query);
+                continue);
+            if checkpoint_state.active_checkpoint == Some(checkpoint_entity) {
+                continue;
+            }
+            checkpoint_state.active_checkpoint = Some(checkpoint_entity);
+            checkpoint_state.snapshot = Some(world_snapshotter.take());
+        }
+    }
+}
+
+fn handle_respawn_at_checkpoint(
+    mut reader: EventReader<RespawnAtCheckpoint>,
+    checkpoint_state: Res<CheckpointState>,
+    mut world_snapshotter: WorldSnapshotter,
+) {
+    if reader.iter().next().is_none() {
+        return;
+    }
+    if let Some(snapshot) = &checkpoint_state.snapshot {
+        world_snapshotter.restore(snapshot);
+    } else {
+        error!("Cannot respawn - no checkpoint was reached");
+    }
+}
+
+fn update_checkpoint_sprites(
+    checkpoint_state: Res<CheckpointState>,
+    mut query: Query<(Entity, &mut Sprite), With<IsCheckpoint>>,
+) {
+    for (entity, mut sprite) in query.iter_mut() {
+        sprite.color = if checkpoint_state.active_checkpoint == Some(entity) {
+            Color::GOLD
+        } else {
+            Color::GRAY
+        };
+    }
+}
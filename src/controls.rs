@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::{DualAxis, InputKind, InputMap, VirtualDPad};
+use serde::{Deserialize, Serialize};
+
+use crate::global_types::InputBinding;
+
+/// The rebindable controls. Unlike [`InputBinding`], movement is split into its directions.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Control {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Pickup,
+    Rewind,
+}
+
+impl Control {
+    pub const ALL: &'static [Control] = &[
+        Control::MoveUp,
+        Control::MoveDown,
+        Control::MoveLeft,
+        Control::MoveRight,
+        Control::Jump,
+        Control::Pickup,
+        Control::Rewind,
+    ];
+
+    pub fn caption(&self) -> &'static str {
+        match self {
+            Control::MoveUp => "Up",
+            Control::MoveDown => "Down",
+            Control::MoveLeft => "Left",
+            Control::MoveRight => "Right",
+            Control::Jump => "Jump",
+            Control::Pickup => "Pickup/Throw",
+            Control::Rewind => "Rewind",
+        }
+    }
+
+    /// The second player's defaults are the keyboard side and gamepad not used by the first.
+    fn default_binding(&self, player_index: usize) -> ControlBinding {
+        let (keys, gamepad_buttons) = match (self, player_index) {
+            (Control::MoveUp, 0) => (
+                vec![KeyCode::Up, KeyCode::W],
+                vec![GamepadButtonType::DPadUp],
+            ),
+            (Control::MoveDown, 0) => (
+                vec![KeyCode::Down, KeyCode::S],
+                vec![GamepadButtonType::DPadDown],
+            ),
+            (Control::MoveLeft, 0) => (
+                vec![KeyCode::Left, KeyCode::A],
+                vec![GamepadButtonType::DPadLeft],
+            ),
+            (Control::MoveRight, 0) => (
+                vec![KeyCode::Right, KeyCode::D],
+                vec![GamepadButtonType::DPadRight],
+            ),
+            (Control::Jump, 0) => (vec![KeyCode::Z], vec![GamepadButtonType::East]),
+            (Control::Pickup, 0) => (vec![KeyCode::Space], vec![GamepadButtonType::South]),
+            (Control::Rewind, 0) => (vec![KeyCode::R], vec![GamepadButtonType::West]),
+            (Control::MoveUp, _) => (vec![KeyCode::Up], vec![GamepadButtonType::DPadUp]),
+            (Control::MoveDown, _) => (vec![KeyCode::Down], vec![GamepadButtonType::DPadDown]),
+            (Control::MoveLeft, _) => (vec![KeyCode::Left], vec![GamepadButtonType::DPadLeft]),
+            (Control::MoveRight, _) => (vec![KeyCode::Right], vec![GamepadButtonType::DPadRight]),
+            (Control::Jump, _) => (vec![KeyCode::RShift], vec![GamepadButtonType::East]),
+            (Control::Pickup, _) => (vec![KeyCode::RControl], vec![GamepadButtonType::South]),
+            (Control::Rewind, _) => (vec![KeyCode::Back], vec![GamepadButtonType::West]),
+        };
+        ControlBinding {
+            keys,
+            gamepad_buttons,
+        }
+    }
+}
+
+/// Reserved for opening and navigating the menus.
+pub const RESERVED_KEYS: &[KeyCode] = &[KeyCode::Escape];
+pub const RESERVED_GAMEPAD_BUTTONS: &[GamepadButtonType] = &[GamepadButtonType::Start];
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct ControlBinding {
+    pub keys: Vec<KeyCode>,
+    pub gamepad_buttons: Vec<GamepadButtonType>,
+}
+
+/// Only controls the player has rebound are stored - the rest use their defaults. Since the
+/// defaults depend on the player, all the methods need to know which player the bindings are for.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ControlBindings(HashMap<Control, ControlBinding>);
+
+impl ControlBindings {
+    pub fn binding(&self, control: Control, player_index: usize) -> ControlBinding {
+        self.0
+            .get(&control)
+            .cloned()
+            .unwrap_or_else(|| control.default_binding(player_index))
+    }
+
+    pub fn set_key(&mut self, control: Control, player_index: usize, key: KeyCode) {
+        let mut binding = self.binding(control, player_index);
+        binding.keys = vec![key];
+        self.0.insert(control, binding);
+    }
+
+    pub fn set_gamepad_button(
+        &mut self,
+        control: Control,
+        player_index: usize,
+        button: GamepadButtonType,
+    ) {
+        let mut binding = self.binding(control, player_index);
+        binding.gamepad_buttons = vec![button];
+        self.0.insert(control, binding);
+    }
+
+    pub fn reset(&mut self) {
+        self.0.clear();
+    }
+
+    /// Controls that share a key or a gamepad button with some other control.
+    pub fn conflicting_controls(&self, player_index: usize) -> Vec<Control> {
+        Control::ALL
+            .iter()
+            .copied()
+            .filter(|control| {
+                let binding = self.binding(*control, player_index);
+                Control::ALL
+                    .iter()
+                    .filter(|other| *other != control)
+                    .any(|other| {
+                        let other_binding = self.binding(*other, player_index);
+                        binding
+                            .keys
+                            .iter()
+                            .any(|key| other_binding.keys.contains(key))
+                            || binding
+                                .gamepad_buttons
+                                .iter()
+                                .any(|button| other_binding.gamepad_buttons.contains(button))
+                    })
+            })
+            .collect()
+    }
+
+    pub fn keys(&self, player_index: usize) -> Vec<KeyCode> {
+        Control::ALL
+            .iter()
+            .flat_map(|control| self.binding(*control, player_index).keys)
+            .collect()
+    }
+
+    /// `excluded_keys` are not added to the map, so that players sharing a keyboard don't control
+    /// each other.
+    pub fn build_input_map(
+        &self,
+        player_index: usize,
+        stick_deadzone: f32,
+        excluded_keys: &[KeyCode],
+    ) -> InputMap<InputBinding> {
+        let mut input_map = InputMap::default();
+
+        let directions = [
+            Control::MoveUp,
+            Control::MoveDown,
+            Control::MoveLeft,
+            Control::MoveRight,
+        ]
+        .map(|control| {
+            let mut binding = self.binding(control, player_index);
+            binding.keys.retain(|key| !excluded_keys.contains(key));
+            binding
+        });
+        // Pair the keys by their order, so that e.g. both the arrows and WASD form a dpad.
+        let num_keyboard_dpads = directions
+            .iter()
+            .map(|binding| binding.keys.len())
+            .max()
+            .unwrap_or(0);
+        for i in 0..num_keyboard_dpads {
+            let [up, down, left, right] = directions.clone().map(|binding| {
+                let key = binding.keys.get(i).or_else(|| binding.keys.last());
+                key.map(|key| InputKind::Keyboard(*key))
+            });
+            if let (Some(up), Some(down), Some(left), Some(right)) = (up, down, left, right) {
+                input_map.insert(
+                    VirtualDPad {
+                        up,
+                        down,
+                        left,
+                        right,
+                    },
+                    InputBinding::Move,
+                );
+            }
+        }
+        let num_gamepad_dpads = directions
+            .iter()
+            .map(|binding| binding.gamepad_buttons.len())
+            .max()
+            .unwrap_or(0);
+        for i in 0..num_gamepad_dpads {
+            let [up, down, left, right] = directions.clone().map(|binding| {
+                let button = binding
+                    .gamepad_buttons
+                    .get(i)
+                    .or_else(|| binding.gamepad_buttons.last());
+                button.map(|button| InputKind::GamepadButton(*button))
+            });
+            if let (Some(up), Some(down), Some(left), Some(right)) = (up, down, left, right) {
+                input_map.insert(
+                    VirtualDPad {
+                        up,
+                        down,
+                        left,
+                        right,
+                    },
+                    InputBinding::Move,
+                );
+            }
+        }
+        input_map.insert(
+            DualAxis::symmetric(
+                GamepadAxisType::LeftStickX,
+                GamepadAxisType::LeftStickY,
+                stick_deadzone,
+            ),
+            InputBinding::Move,
+        );
+
+        for (control, input_binding) in [
+            (Control::Jump, InputBinding::Jump),
+            (Control::Pickup, InputBinding::Pickup),
+            (Control::Rewind, InputBinding::Rewind),
+        ] {
+            let binding = self.binding(control, player_index);
+            for key in binding.keys {
+                if excluded_keys.contains(&key) {
+                    continue;
+                }
+                input_map.insert(key, input_binding);
+            }
+            for button in binding.gamepad_buttons {
+                input_map.insert(button, input_binding);
+            }
+        }
+
+        input_map.build()
+    }
+}
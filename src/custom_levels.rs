@@ -0,0 +1,84 @@
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use bevy_pkv::PkvStore;
+
+use crate::utils::some_or;
+
+pub struct CustomLevelsPlugin {
+    /// Overrides the directory saved from the menu.
+    pub levels_dir: Option<PathBuf>,
+}
+
+impl Plugin for CustomLevelsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CustomLevels>();
+        let levels_dir = self.levels_dir.clone();
+        app.add_startup_system(
+            move |pkv: Res<PkvStore>, mut custom_levels: ResMut<CustomLevels>| {
+                let dir = levels_dir.clone().or_else(|| {
+                    pkv.get::<String>(CUSTOM_LEVELS_DIR_PKV_KEY)
+                        .ok()
+                        .map(Into::into)
+                });
+                if let Some(dir) = dir {
+                    custom_levels.set_dir(dir);
+                }
+            },
+        );
+    }
+}
+
+const CUSTOM_LEVELS_DIR_PKV_KEY: &str = "custom_levels_dir";
+
+/// Custom levels are identified by their absolute path, which bundled level filenames never are.
+pub fn is_custom_level(level: &str) -> bool {
+    Path::new(level).is_absolute()
+}
+
+/// Levels that are not part of the game's packs. They can be played, but do not count toward
+/// progression.
+#[derive(Default)]
+pub struct CustomLevels {
+    dir: Option<PathBuf>,
+    /// Absolute paths, sorted.
+    levels: Vec<String>,
+}
+
+impl CustomLevels {
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    pub fn levels(&self) -> &[String] {
+        &self.levels
+    }
+
+    pub fn set_dir(&mut self, dir: PathBuf) {
+        let dir = dir.canonicalize().unwrap_or(dir);
+        self.levels = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| {
+                    let path = entry.ok()?.path();
+                    if path.extension()? != "yol" {
+                        return None;
+                    }
+                    Some(path.to_str()?.to_owned())
+                })
+                .collect(),
+            Err(err) => {
+                error!("Cannot read custom levels from {:?}: {}", dir, err);
+                Vec::new()
+            }
+        };
+        self.levels.sort();
+        self.dir = Some(dir);
+    }
+
+    pub fn save_dir(&self, pkv: &mut PkvStore) {
+        let dir = some_or!(self.dir.as_ref().and_then(|dir| dir.to_str()); return);
+        if let Err(err) = pkv.set(CUSTOM_LEVELS_DIR_PKV_KEY, &dir.to_owned()) {
+            error!("Cannot save custom levels directory: {}", err);
+        }
+    }
+}
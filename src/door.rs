@@ -1,12 +1,11 @@
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
-use bevy_rapier2d::rapier::prelude::CollisionEventFlags;
 use bevy_yoleck::{YoleckExtForApp, YoleckPopulate, YoleckTypeHandler};
 use serde::{Deserialize, Serialize};
 
 use crate::global_types::{AppState, IsDoorKey, IsPlayer, OpenableDoor};
 use crate::loading::GameAssets;
-use crate::utils::{entities_ordered_by_type, some_or};
+use crate::world_snapshot::Retired;
 
 pub struct DoorPlugin;
 
@@ -20,13 +19,18 @@ impl Plugin for DoorPlugin {
                     -0.1,
                 ))
         });
+        app.add_event::<DoorOpened>();
         app.add_system_set({
-            SystemSet::on_update(AppState::Game).with_system(handle_opening_when_keys_are_taken)
+            SystemSet::on_update(AppState::Game)
+                .with_system(handle_opening_when_keys_are_taken)
+                // A player killed on the same frame the others reach the door loses the level
+                .with_system(handle_player_enters.after(crate::hazard::handle_player_killed))
         });
-        app.add_system(handle_player_enters);
     }
 }
 
+pub struct DoorOpened;
+
 #[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct Door {
     #[serde(default)]
@@ -52,33 +56,39 @@ fn populate(mut populate: YoleckPopulate<Door>, game_assets: Res<GameAssets>) {
 }
 
 fn handle_opening_when_keys_are_taken(
-    keys_query: Query<(), With<IsDoorKey>>,
+    keys_query: Query<(), (With<IsDoorKey>, Without<Retired>)>,
     mut door_query: Query<(&mut OpenableDoor, &mut TextureAtlasSprite)>,
+    mut door_opened_writer: EventWriter<DoorOpened>,
 ) {
     let should_be_open = keys_query.is_empty();
     let sprite_index = if should_be_open { 1 } else { 0 };
     for (mut door, mut sprite) in door_query.iter_mut() {
+        if should_be_open && !door.open {
+            door_opened_writer.send(DoorOpened);
+        }
         door.open = should_be_open;
         sprite.index = sprite_index;
     }
 }
 
+/// In co-op, the level is only completed when all the players are in the door.
 fn handle_player_enters(
-    mut reader: EventReader<CollisionEvent>,
-    player_query: Query<(), With<IsPlayer>>,
-    door_query: Query<&OpenableDoor>,
+    player_query: Query<Entity, With<IsPlayer>>,
+    door_query: Query<(Entity, &OpenableDoor)>,
+    rapier_context: Res<RapierContext>,
     mut state: ResMut<State<AppState>>,
 ) {
-    for event in reader.iter() {
-        if let &CollisionEvent::Started(e1, e2, CollisionEventFlags::SENSOR) = event {
-            let [_player_entity, door_entity] = some_or!(
-                entities_ordered_by_type!([e1, e2], player_query, door_query);
-                continue);
-            let door = door_query.get(door_entity).unwrap();
-            if door.open {
-                state.set(AppState::LevelCompleted).unwrap();
-                return;
-            }
+    if player_query.is_empty() {
+        return;
+    }
+    let all_players_entered = player_query.iter().all(|player_entity| {
+        door_query.iter().any(|(door_entity, door)| {
+            door.open && rapier_context.intersection_pair(player_entity, door_entity) == Some(true)
+        })
+    });
+    if all_players_entered {
+        if let Err(err) = state.set(AppState::LevelCompleted) {
+            warn!("Unable to complete level: {}", err);
         }
     }
 }
@@ -7,6 +7,7 @@ use serde::{Deserialize, Serialize};
 use crate::global_types::{IsDoorKey, IsPlayer};
 use crate::loading::GameAssets;
 use crate::utils::{entities_ordered_by_type, some_or};
+use crate::world_snapshot::{Retire, Retired};
 
 pub struct DoorKeyPlugin;
 
@@ -20,10 +21,13 @@ impl Plugin for DoorKeyPlugin {
                     0.0,
                 ))
         });
+        app.add_event::<DoorKeyCollected>();
         app.add_system(handle_taken_by_player);
     }
 }
 
+pub struct DoorKeyCollected;
+
 #[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct DoorKey {
     #[serde(default)]
@@ -51,15 +55,17 @@ fn populate(mut populate: YoleckPopulate<DoorKey>, game_assets: Res<GameAssets>)
 fn handle_taken_by_player(
     mut reader: EventReader<CollisionEvent>,
     player_query: Query<(), With<IsPlayer>>,
-    door_key_query: Query<(), With<IsDoorKey>>,
+    door_key_query: Query<(), (With<IsDoorKey>, Without<Retired>)>,
     mut commands: Commands,
+    mut door_key_collected_writer: EventWriter<DoorKeyCollected>,
 ) {
     for event in reader.iter() {
         if let &CollisionEvent::Started(e1, e2, CollisionEventFlags::SENSOR) = event {
             let [_player_entity, door_key_entity] = some_or!(
                 entities_ordered_by_type!([e1, e2], player_query, door_key_query);
                 continue);
-            commands.entity(door_key_entity).despawn_recursive();
+            commands.entity(door_key_entity).insert(Retire);
+            door_key_collected_writer.send(DoorKeyCollected);
         }
     }
 }
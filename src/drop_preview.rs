@@ -0,0 +1,146 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::global_types::{AppState, Carrier, HDirection, InputBinding, IsMountBase, IsPlayer};
+use crate::parts_manipulation::{throw_direction, throw_offset};
+use crate::player_control::Stunned;
+use crate::utils::some_or;
+
+pub struct DropPreviewPlugin;
+
+impl Plugin for DropPreviewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_update(AppState::Game).with_system(update_drop_previews));
+        // Previews are not level entities, so they must be cleaned up explicitly. Leaving the game
+        // (e.g. to the pause menu) hides them - they are recreated when it resumes.
+        app.add_system_set(SystemSet::on_exit(AppState::Game).with_system(despawn_drop_previews));
+        app.add_system_set(
+            SystemSet::on_enter(AppState::LoadLevel).with_system(despawn_drop_previews),
+        );
+    }
+}
+
+/// A ghost of the carried part, shown where it will land if the player throws it now.
+#[derive(Component)]
+struct DropPreview {
+    player_entity: Entity,
+}
+
+const PREVIEW_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.4);
+/// Used when the part will land on a free mount base and get mounted on it.
+const PREVIEW_MOUNT_COLOR: Color = Color::rgba(0.4, 1.0, 0.4, 0.6);
+
+fn despawn_drop_previews(query: Query<Entity, With<DropPreview>>, mut commands: Commands) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_drop_previews(
+    players_query: Query<
+        (Entity, &ActionState<InputBinding>, &Carrier, &HDirection),
+        (With<IsPlayer>, Without<Stunned>),
+    >,
+    carried_query: Query<
+        (
+            &GlobalTransform,
+            &TextureAtlasSprite,
+            &Handle<TextureAtlas>,
+            Option<&Children>,
+        ),
+        Without<DropPreview>,
+    >,
+    mut previews_query: Query<(
+        Entity,
+        &DropPreview,
+        &mut Transform,
+        &mut TextureAtlasSprite,
+        &mut Handle<TextureAtlas>,
+    )>,
+    mount_bases_query: Query<(&GlobalTransform, &Carrier), With<IsMountBase>>,
+    rapier_context: Res<RapierContext>,
+    mut commands: Commands,
+) {
+    let mut shown_previews = HashSet::new();
+    for (player_entity, action_state, carrier, facing) in players_query.iter() {
+        let carried_entity = some_or!(carrier.carrying; continue);
+        let direction = throw_direction(action_state, facing);
+        let (carried_transform, carried_sprite, carried_atlas, carried_children) =
+            some_or!(carried_query.get(carried_entity).ok(); continue);
+        let position = carried_transform.translation() + throw_offset(direction);
+
+        let ignore: HashSet<Entity> = [player_entity, carried_entity]
+            .into_iter()
+            .chain(
+                carried_children
+                    .into_iter()
+                    .flat_map(|children| children.iter().copied()),
+            )
+            .collect();
+        let will_mount = rapier_context
+            .cast_ray(
+                position.truncate(),
+                Vec2::NEG_Y,
+                2.0,
+                true,
+                QueryFilter::default()
+                    .exclude_sensors()
+                    .predicate(&|other_entity| !ignore.contains(&other_entity)),
+            )
+            .and_then(|(hit_entity, _)| mount_bases_query.get(hit_entity).ok())
+            .map_or(false, |(mount_base_transform, mount_base_carrier)| {
+                // Same conditions as in mount detection
+                mount_base_carrier.carrying.is_none()
+                    && (mount_base_transform.translation().x - position.x).abs() <= 0.5
+            });
+
+        let sprite = TextureAtlasSprite {
+            index: carried_sprite.index,
+            flip_x: carried_sprite.flip_x,
+            custom_size: carried_sprite.custom_size,
+            color: if will_mount {
+                PREVIEW_MOUNT_COLOR
+            } else {
+                PREVIEW_COLOR
+            },
+            ..Default::default()
+        };
+        let transform = Transform::from_translation(position);
+
+        if let Some((
+            preview_entity,
+            _,
+            mut preview_transform,
+            mut preview_sprite,
+            mut preview_atlas,
+        )) = previews_query
+            .iter_mut()
+            .find(|(_, preview, _, _, _)| preview.player_entity == player_entity)
+        {
+            *preview_transform = transform;
+            *preview_sprite = sprite;
+            if *preview_atlas != *carried_atlas {
+                *preview_atlas = carried_atlas.clone();
+            }
+            shown_previews.insert(preview_entity);
+        } else {
+            let mut cmd = commands.spawn();
+            cmd.insert_bundle(SpriteSheetBundle {
+                sprite,
+                texture_atlas: carried_atlas.clone(),
+                transform,
+                ..Default::default()
+            });
+            cmd.insert(DropPreview { player_entity });
+        }
+    }
+    for (preview_entity, _, _, _, _) in previews_query.iter() {
+        if !shown_previews.contains(&preview_entity) {
+            commands.entity(preview_entity).despawn_recursive();
+        }
+    }
+}
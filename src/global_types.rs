@@ -16,8 +16,11 @@ pub enum AppState {
 
 #[derive(Clone, Hash, Debug, PartialEq, Eq)]
 pub enum MenuState {
+    ProfileSelect,
     Main,
     LevelSelect,
+    Settings,
+    Controls,
     Pause,
     LevelCompleted,
     GameOver,
@@ -31,14 +34,16 @@ pub enum MenuState {
 pub struct LevelProgress {
     pub just_completed: Option<String>,
     pub current_level: Option<String>,
-    pub num_levels_available: usize,
+    pub save_data_loaded: bool,
 }
 
 #[derive(Actionlike, Debug, Copy, Clone, PartialEq, Eq, Hash)]
 // #[allow(dead_code)]
 pub enum InputBinding {
     Move,
+    Jump,
     Pickup,
+    Rewind,
 }
 
 #[derive(Component)]
@@ -47,6 +52,10 @@ pub struct CameraInclude;
 #[derive(Component)]
 pub struct IsPlayer;
 
+/// Which player this is, for players that have their own controls.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PlayerIndex(pub usize);
+
 #[derive(Component, Default)]
 pub struct Pickable {
     pub carried_by: Option<Entity>,
@@ -0,0 +1,131 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use bevy_yoleck::{YoleckEdit, YoleckExtForApp, YoleckPopulate, YoleckTypeHandler};
+use serde::{Deserialize, Serialize};
+
+use crate::camera::LevelBounds;
+use crate::global_types::{AppState, CameraInclude, IsPlayer, MenuState, PlayerIndex};
+use crate::utils::{entities_ordered_by_type, some_or};
+
+pub struct HazardPlugin;
+
+impl Plugin for HazardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlayerKilled>();
+        app.add_yoleck_handler({
+            YoleckTypeHandler::<Spikes>::new("Spikes")
+                .populate_with(populate_spikes)
+                .with(crate::yoleck_utils::position_adapter(
+                    |spikes: &mut Spikes| (&mut spikes.position, spikes.size),
+                    0.0,
+                ))
+                .edit_with(edit_spikes)
+        });
+        app.add_system_set({
+            SystemSet::on_update(AppState::Game)
+                .with_system(handle_touching_spikes)
+                .with_system(detect_falling_out_of_level)
+                .with_system(handle_player_killed)
+        });
+    }
+}
+
+#[derive(Debug)]
+pub struct PlayerKilled {
+    pub player_entity: Entity,
+}
+
+#[derive(Component)]
+pub struct IsHazard;
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Spikes {
+    #[serde(default)]
+    position: Vec2,
+    #[serde(default = "default_size")]
+    size: IVec2,
+}
+
+fn default_size() -> IVec2 {
+    IVec2::new(1, 1)
+}
+
+fn populate_spikes(mut populate: YoleckPopulate<Spikes>) {
+    populate.populate(|_ctx, data, mut cmd| {
+        cmd.insert_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: Color::CRIMSON,
+                custom_size: Some(data.size.as_vec2()),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        cmd.insert(RigidBody::Fixed);
+        cmd.insert(Collider::cuboid(
+            data.size.x as f32 * 0.5,
+            data.size.y as f32 * 0.5,
+        ));
+        cmd.insert(ActiveEvents::COLLISION_EVENTS);
+        cmd.insert(IsHazard);
+        cmd.insert(CameraInclude);
+    });
+}
+
+fn edit_spikes(mut edit: YoleckEdit<Spikes>, mut commands: Commands) {
+    edit.edit(|ctx, data, ui| {
+        crate::yoleck_utils::edit_size(ctx, &mut commands, ui, &mut data.position, &mut data.size);
+    });
+}
+
+fn handle_touching_spikes(
+    mut reader: EventReader<CollisionEvent>,
+    player_query: Query<(), With<IsPlayer>>,
+    hazard_query: Query<(), With<IsHazard>>,
+    mut player_killed_writer: EventWriter<PlayerKilled>,
+) {
+    for event in reader.iter() {
+        if let &CollisionEvent::Started(e1, e2, _) = event {
+            let [player_entity, _hazard_entity] = some_or!(
+                entities_ordered_by_type!([e1, e2], player_query, hazard_query);
+                continue);
+            player_killed_writer.send(PlayerKilled { player_entity });
+        }
+    }
+}
+
+/// How far below the bottom of the level the player needs to fall before being considered dead.
+const PIT_DEPTH: f32 = 2.0;
+
+fn detect_falling_out_of_level(
+    level_bounds: Res<LevelBounds>,
+    player_query: Query<(Entity, &GlobalTransform), With<IsPlayer>>,
+    mut player_killed_writer: EventWriter<PlayerKilled>,
+) {
+    let [_, bottom, _, _] = some_or!(level_bounds.0; return);
+    for (player_entity, global_transform) in player_query.iter() {
+        if global_transform.translation().y < bottom - PIT_DEPTH {
+            player_killed_writer.send(PlayerKilled { player_entity });
+        }
+    }
+}
+
+pub(crate) fn handle_player_killed(
+    mut reader: EventReader<PlayerKilled>,
+    player_query: Query<&PlayerIndex>,
+    mut state: ResMut<State<AppState>>,
+) {
+    let mut any_killed = false;
+    for PlayerKilled { player_entity } in reader.iter() {
+        any_killed = true;
+        if let Ok(player_index) = player_query.get(*player_entity) {
+            info!("Player {} was killed", player_index.0 + 1);
+        }
+    }
+    if any_killed {
+        // Something else (e.g. the pause menu) may have already queued a transition on this very
+        // frame.
+        if let Err(err) = state.set(AppState::Menu(MenuState::GameOver)) {
+            warn!("Unable to end game after player was killed: {}", err);
+        }
+    }
+}
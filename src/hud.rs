@@ -0,0 +1,72 @@
+use bevy::prelude::*;
+use bevy_egui_kbgp::bevy_egui::EguiContext;
+use bevy_egui_kbgp::egui;
+
+use crate::global_types::{
+    Activatable, AppState, Carrier, IsDoorKey, LevelProgress, OpenableDoor, PlayerIndex,
+};
+use crate::level_stats::{format_time, CurrentRunStats};
+use crate::menu::format_level_name;
+use crate::robot_part::RobotPartType;
+use crate::world_snapshot::Retired;
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_update(AppState::Game).with_system(show_hud));
+    }
+}
+
+/// A strip at the top of the screen. Being a panel, the camera keeps the level out from under it.
+fn show_hud(
+    mut egui_context: ResMut<EguiContext>,
+    level_progress: Res<LevelProgress>,
+    current_run_stats: Res<CurrentRunStats>,
+    players_query: Query<(&PlayerIndex, &Carrier)>,
+    parts_query: Query<(&RobotPartType, Option<&Activatable>), Without<Retired>>,
+    keys_query: Query<(), (With<IsDoorKey>, Without<Retired>)>,
+    door_query: Query<&OpenableDoor>,
+) {
+    let mut players: Vec<_> = players_query.iter().collect();
+    players.sort_by_key(|(player_index, _)| player_index.0);
+    let keys_remaining = keys_query.iter().count();
+    let door_open = door_query.iter().any(|door| door.open);
+
+    egui::TopBottomPanel::top("hud").show(egui_context.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            if let Some(current_level) = level_progress.current_level.as_ref() {
+                ui.label(format_level_name(current_level));
+                ui.separator();
+            }
+            ui.label(format_time(current_run_stats.stats.time));
+            ui.separator();
+            for (player_index, carrier) in players.iter() {
+                if 1 < players.len() {
+                    ui.label(format!("P{}:", player_index.0 + 1));
+                }
+                match carrier
+                    .carrying
+                    .and_then(|entity| parts_query.get(entity).ok())
+                {
+                    Some((part_type, activatable)) => {
+                        let powered = activatable.map(|activatable| activatable.active);
+                        ui.label(format!("Carrying: {:?}", part_type));
+                        if powered == Some(true) {
+                            ui.colored_label(egui::Color32::YELLOW, "(powered)");
+                        }
+                    }
+                    None => {
+                        ui.label("Carrying: nothing");
+                    }
+                }
+                ui.separator();
+            }
+            if door_open {
+                ui.colored_label(egui::Color32::GREEN, "Door open");
+            } else {
+                ui.label(format!("Keys left: {}", keys_remaining));
+            }
+        });
+    });
+}
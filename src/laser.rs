@@ -1,15 +1,26 @@
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
-use crate::global_types::AppState;
+use crate::global_types::{AppState, IsPlayer};
+use crate::hazard::PlayerKilled;
+use crate::player_control::Stunned;
+use crate::world_snapshot::{Retire, Retired};
 
 pub struct LaserPlugin;
 
 impl Plugin for LaserPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<TriggerLaserShot>();
+        app.add_event::<LaserHit>();
+        app.insert_resource(LaserDamageSettings {
+            damage: 0.3,
+            knockback: 3.0,
+            player_stun_duration: 1.0,
+            kill_stunned_player: true,
+        });
         app.add_system(shoot_laser);
         app.add_system(handle_laser_hits);
+        app.add_system(update_breakable_transparency);
         app.add_system_set(SystemSet::on_update(AppState::Game).with_system(dispose_laser));
     }
 }
@@ -47,6 +58,16 @@ fn shoot_laser(mut reader: EventReader<TriggerLaserShot>, mut commands: Commands
     }
 }
 
+/// Sent when a laser shot hits something and disappears.
+#[derive(Debug)]
+pub struct LaserHit {
+    pub position: Vec2,
+    pub direction: Vec2,
+    pub target_entity: Entity,
+    /// Whether the hit destroyed a [`Breakable`].
+    pub destroyed: bool,
+}
+
 #[derive(Component)]
 pub struct Laser {
     ignore_entity: Entity,
@@ -63,10 +84,19 @@ fn dispose_laser(query: Query<(Entity, &Laser, &Transform)>, mut commands: Comma
     }
 }
 
+pub struct LaserDamageSettings {
+    /// How much life a [`Breakable`] loses on each hit.
+    pub damage: f32,
+    /// Speed added to dynamic bodies in the direction of the laser shot.
+    pub knockback: f32,
+    pub player_stun_duration: f32,
+    /// Whether hitting a player that is still stunned should kill them.
+    pub kill_stunned_player: bool,
+}
+
 #[derive(Component)]
 pub struct Breakable {
-    #[allow(unused)]
-    life: f32,
+    pub life: f32,
 }
 
 impl Default for Breakable {
@@ -75,24 +105,79 @@ impl Default for Breakable {
     }
 }
 
+fn update_breakable_transparency(
+    mut query: Query<
+        (&Breakable, AnyOf<(&mut Sprite, &mut TextureAtlasSprite)>),
+        Changed<Breakable>,
+    >,
+) {
+    for (breakable, (sprite, atlas_sprite)) in query.iter_mut() {
+        let alpha = 1.0 - 0.9 * (1.0 - breakable.life);
+        if let Some(mut sprite) = sprite {
+            sprite.color.set_a(alpha);
+        }
+        if let Some(mut atlas_sprite) = atlas_sprite {
+            atlas_sprite.color.set_a(alpha);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn handle_laser_hits(
-    laser_query: Query<(Entity, &Laser)>,
+    laser_query: Query<(Entity, &Laser, &Velocity, &GlobalTransform)>,
     rapier_context: Res<RapierContext>,
     mut commands: Commands,
-    mut breakable_query: Query<(&mut Breakable, &mut Sprite)>,
+    laser_damage_settings: Res<LaserDamageSettings>,
+    mut breakable_query: Query<&mut Breakable, Without<Retired>>,
+    mut knockback_query: Query<(&RigidBody, &mut Velocity), Without<Laser>>,
+    player_query: Query<Option<&Stunned>, With<IsPlayer>>,
+    mut player_killed_writer: EventWriter<PlayerKilled>,
+    mut laser_hit_writer: EventWriter<LaserHit>,
 ) {
-    for (laser_entity, laser) in laser_query.iter() {
-        for (e1, e2, _) in rapier_context.intersections_with(laser_entity) {
+    for (laser_entity, laser, laser_velocity, laser_transform) in laser_query.iter() {
+        for (e1, e2, intersecting) in rapier_context.intersections_with(laser_entity) {
+            if !intersecting {
+                continue;
+            }
             let other_entity = if e1 == laser_entity { e2 } else { e1 };
             if other_entity == laser.ignore_entity {
                 continue;
             }
             commands.entity(laser_entity).despawn_recursive();
-            if let Ok((mut breakable, mut sprite)) = breakable_query.get_mut(other_entity) {
-                breakable.life -= 0.3;
-                sprite.color.set_a(1.0 - 0.9 * (1.0 - breakable.life));
+            let mut laser_hit = LaserHit {
+                position: laser_transform.translation().truncate(),
+                direction: laser_velocity.linvel.normalize_or_zero(),
+                target_entity: other_entity,
+                destroyed: false,
+            };
+            if let Ok(mut breakable) = breakable_query.get_mut(other_entity) {
+                breakable.life -= laser_damage_settings.damage;
                 if breakable.life <= 0.0 {
-                    commands.entity(other_entity).despawn_recursive();
+                    commands.entity(other_entity).insert(Retire);
+                    laser_hit.destroyed = true;
+                    laser_hit_writer.send(laser_hit);
+                    continue;
+                }
+            }
+            laser_hit_writer.send(laser_hit);
+            if let Ok((rigid_body, mut velocity)) = knockback_query.get_mut(other_entity) {
+                if *rigid_body == RigidBody::Dynamic {
+                    velocity.linvel +=
+                        laser_damage_settings.knockback * laser_velocity.linvel.normalize_or_zero();
+                }
+            }
+            if let Ok(stunned) = player_query.get(other_entity) {
+                if stunned.is_some() && laser_damage_settings.kill_stunned_player {
+                    player_killed_writer.send(PlayerKilled {
+                        player_entity: other_entity,
+                    });
+                } else {
+                    commands
+                        .entity(other_entity)
+                        .insert(Stunned(Timer::from_seconds(
+                            laser_damage_settings.player_stun_duration,
+                            false,
+                        )));
                 }
             }
         }
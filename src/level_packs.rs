@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use bevy::asset::{AssetLoader, AssetPath, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use bevy_yoleck::YoleckLevelIndex;
+use serde::Deserialize;
+
+pub struct LevelPacksPlugin;
+
+impl Plugin for LevelPacksPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<LevelPacks>();
+        app.init_asset_loader::<LevelPacksLoader>();
+    }
+}
+
+/// All the level packs of the game, in the order they appear in the level select menu.
+///
+/// Level filenames are used as keys in the save data, so they must be unique across all packs.
+#[derive(TypeUuid)]
+#[uuid = "6a0d9a8e-3f5c-4c2e-9b4a-2f1d7e8c5b13"]
+pub struct LevelPacks(pub Vec<LevelPack>);
+
+#[derive(Deserialize)]
+pub struct LevelPack {
+    pub name: String,
+    /// Path of the pack's `.yoli` file, relative to the packs file.
+    pub index: String,
+    #[serde(skip)]
+    pub index_handle: Handle<YoleckLevelIndex>,
+    /// Total stars needed before the first level of the pack becomes available.
+    #[serde(default)]
+    pub required_stars: u32,
+    /// Keyed by level filename. Levels not listed here get the default layout and unlocks.
+    #[serde(default)]
+    pub levels: HashMap<String, LevelNode>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct LevelNode {
+    /// In world map cells.
+    pub map_position: Option<[f32; 2]>,
+    /// Filenames of the levels that become available when this level is completed. When not
+    /// specified, completing the level unlocks the next regular level in the pack's index.
+    pub unlocks: Option<Vec<String>>,
+    /// Makes this a bonus level, which becomes available once this many stars are earned in total
+    /// instead of by completing other levels. Bonus levels are never picked as the next level.
+    pub required_stars: Option<u32>,
+}
+
+impl LevelPacks {
+    pub fn find_level<'a>(
+        &'a self,
+        level_index_assets: &'a Assets<YoleckLevelIndex>,
+        filename: &str,
+    ) -> Option<(&'a LevelPack, &'a YoleckLevelIndex)> {
+        self.0.iter().find_map(|pack| {
+            let level_index = level_index_assets.get(&pack.index_handle)?;
+            level_index
+                .iter()
+                .any(|level| level.filename == filename)
+                .then_some((pack, level_index))
+        })
+    }
+}
+
+impl LevelPack {
+    pub fn bonus_level_star_requirement(&self, filename: &str) -> Option<u32> {
+        self.levels.get(filename)?.required_stars
+    }
+
+    pub fn unlocked_by(&self, level_index: &YoleckLevelIndex, filename: &str) -> Vec<String> {
+        if let Some(unlocks) = self
+            .levels
+            .get(filename)
+            .and_then(|node| node.unlocks.as_ref())
+        {
+            return unlocks.clone();
+        }
+        level_index
+            .iter()
+            .skip_while(|level| level.filename != filename)
+            .skip(1)
+            .find(|level| self.bonus_level_star_requirement(&level.filename).is_none())
+            .map(|level| vec![level.filename.clone()])
+            .unwrap_or_default()
+    }
+
+    /// `index` is the position of the level in the pack's index. Levels without an explicit
+    /// position are laid out in a snake pattern, four in each row.
+    pub fn map_position(&self, index: usize, filename: &str) -> Vec2 {
+        if let Some([x, y]) = self.levels.get(filename).and_then(|node| node.map_position) {
+            return Vec2::new(x, y);
+        }
+        let row = index / 4;
+        let column = if row % 2 == 0 {
+            index % 4
+        } else {
+            3 - index % 4
+        };
+        Vec2::new(column as f32, row as f32)
+    }
+}
+
+#[derive(Default)]
+pub struct LevelPacksLoader;
+
+impl AssetLoader for LevelPacksLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let mut packs: Vec<LevelPack> = serde_json::from_slice(bytes)?;
+            let base_dir = load_context
+                .path()
+                .parent()
+                .unwrap_or_else(|| Path::new(""));
+            let mut dependencies = Vec::new();
+            for pack in packs.iter_mut() {
+                let index_path = AssetPath::new(base_dir.join(&pack.index), None);
+                pack.index_handle = load_context.get_handle(index_path.clone());
+                dependencies.push(index_path);
+            }
+            load_context.set_default_asset(
+                LoadedAsset::new(LevelPacks(packs)).with_dependencies(dependencies),
+            );
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["packs.json"]
+    }
+}
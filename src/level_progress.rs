@@ -1,8 +1,18 @@
+use std::collections::HashMap;
+
 use bevy::prelude::*;
 use bevy_pkv::PkvStore;
 use bevy_yoleck::YoleckLevelIndex;
+use serde::{Deserialize, Serialize};
 
+use crate::custom_levels::is_custom_level;
 use crate::global_types::{AppState, LevelProgress, MenuState};
+use crate::level_packs::{LevelPack, LevelPacks};
+use crate::level_stats::PersonalBest;
+use crate::loading::GameAssets;
+use crate::profile::{
+    profile_pkv_key, ActiveProfile, ProfileCopied, ProfileDeleted, FIRST_PROFILE_ID,
+};
 use crate::utils::some_or;
 
 pub struct LevelProgressPlugin;
@@ -12,94 +22,244 @@ impl Plugin for LevelProgressPlugin {
         app.insert_resource(LevelProgress {
             just_completed: None,
             current_level: None,
-            num_levels_available: 0,
+            save_data_loaded: false,
         });
-        app.add_system(read_last_finished_level);
+        app.init_resource::<SaveData>();
+        app.add_system(read_save_data);
+        app.add_system(copy_profile_save_data);
+        app.add_system(clear_deleted_profile_save_data);
         app.add_system_set(
             SystemSet::on_update(AppState::LevelCompleted).with_system(handle_level_completion),
         );
     }
 }
 
-const LEVEL_PKV_KEY: &str = "completed_up_to_level";
+const SAVE_DATA_PKV_KEY: &str = "save_data";
+const SAVE_DATA_VERSION: u32 = 1;
+
+/// Before [`SaveData`] and profiles, only the filename of the furthest completed level was stored.
+const LEGACY_LEVEL_PKV_KEY: &str = "completed_up_to_level";
+/// Before [`SaveData`] and profiles, personal bests were stored separately, keyed by level filename.
+const LEGACY_LEVEL_STATS_PKV_KEY: &str = "level_stats";
+
+#[derive(Serialize, Deserialize)]
+pub struct SaveData {
+    pub version: u32,
+    /// Keyed by level filename, so that reordering the levels in the index does not affect it.
+    pub levels: HashMap<String, LevelRecord>,
+}
+
+impl Default for SaveData {
+    fn default() -> Self {
+        Self {
+            version: SAVE_DATA_VERSION,
+            levels: Default::default(),
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct LevelRecord {
+    pub completed: bool,
+    pub unlocked: bool,
+    pub best: Option<PersonalBest>,
+}
+
+impl SaveData {
+    /// Save data written by a newer version of the game may have fields this version does not
+    /// know about. It is still used, but never written back, so that those fields are not lost.
+    pub fn is_read_only(&self) -> bool {
+        SAVE_DATA_VERSION < self.version
+    }
+
+    pub fn level(&self, filename: &str) -> Option<&LevelRecord> {
+        self.levels.get(filename)
+    }
+
+    pub fn level_mut(&mut self, filename: &str) -> &mut LevelRecord {
+        self.levels.entry(filename.to_owned()).or_default()
+    }
+
+    pub fn best(&self, filename: &str) -> Option<&PersonalBest> {
+        self.level(filename)?.best.as_ref()
+    }
+
+    pub fn total_stars(&self) -> u32 {
+        self.levels
+            .values()
+            .filter_map(|level| level.best.as_ref())
+            .filter_map(|best| best.stars)
+            .map(u32::from)
+            .sum()
+    }
+
+    /// `index` is the position of the level in the pack's index. The first level of each pack is
+    /// unlocked once the pack itself is.
+    pub fn is_unlocked(&self, pack: &LevelPack, index: usize, filename: &str) -> bool {
+        if let Some(star_requirement) = pack.bonus_level_star_requirement(filename) {
+            return star_requirement <= self.total_stars();
+        }
+        (index == 0 && self.is_pack_unlocked(pack))
+            || self
+                .level(filename)
+                .map(|level| level.unlocked || level.completed)
+                .unwrap_or(false)
+    }
+
+    pub fn is_pack_unlocked(&self, pack: &LevelPack) -> bool {
+        pack.required_stars <= self.total_stars()
+    }
+
+    pub fn save(&self, pkv: &mut PkvStore, active_profile: &ActiveProfile) {
+        if self.is_read_only() {
+            error!("Not saving level progression - it was saved by a newer version of the game");
+            return;
+        }
+        let key = some_or!(active_profile.pkv_key(SAVE_DATA_PKV_KEY); {
+            error!("Cannot save level progression - no profile is active");
+            return;
+        });
+        if let Err(err) = pkv.set(&key, self) {
+            error!("Cannot save level progression: {}", err);
+        }
+    }
+}
 
-fn read_last_finished_level(
-    pkv: Res<PkvStore>,
+fn read_save_data(
+    mut pkv: ResMut<PkvStore>,
     mut level_progress: ResMut<LevelProgress>,
+    mut save_data: ResMut<SaveData>,
     asset_server: Res<AssetServer>,
     level_index_assets: Res<Assets<YoleckLevelIndex>>,
+    active_profile: Res<ActiveProfile>,
 ) {
-    if 0 < level_progress.num_levels_available {
+    if active_profile.is_changed() {
+        level_progress.save_data_loaded = false;
+    }
+    if level_progress.save_data_loaded {
         return;
     }
-    if let Ok(completed_up_to_level) = pkv.get::<String>(LEVEL_PKV_KEY) {
+    let profile_id = some_or!(active_profile.0; return);
+    if let Ok(loaded) = pkv.get::<SaveData>(&profile_pkv_key(profile_id, SAVE_DATA_PKV_KEY)) {
+        if loaded.is_read_only() {
+            error!(
+                "Save data version {} is newer than {} - progress will not be saved",
+                loaded.version, SAVE_DATA_VERSION
+            );
+        }
+        *save_data = loaded;
+    } else if profile_id == FIRST_PROFILE_ID {
         let level_index =
             some_or!(level_index_assets.get(&asset_server.load("levels/index.yoli")); return);
-        if let Some(index) = level_index.iter().enumerate().find_map(|(index, level)| {
-            if level.filename == completed_up_to_level {
-                Some(index)
-            } else {
-                None
+        *save_data = migrate_legacy_save_data(&pkv, level_index);
+        save_data.save(&mut pkv, &active_profile);
+    } else {
+        *save_data = Default::default();
+    }
+    level_progress.save_data_loaded = true;
+}
+
+fn migrate_legacy_save_data(pkv: &PkvStore, level_index: &YoleckLevelIndex) -> SaveData {
+    let mut save_data = SaveData::default();
+    if let Ok(completed_up_to_level) = pkv.get::<String>(LEGACY_LEVEL_PKV_KEY) {
+        if let Some(position) = level_index
+            .iter()
+            .position(|level| level.filename == completed_up_to_level)
+        {
+            for level in level_index.iter().take(position + 1) {
+                let level_record = save_data.level_mut(&level.filename);
+                level_record.completed = true;
+                level_record.unlocked = true;
+            }
+            if let Some(next_level) = level_index.iter().nth(position + 1) {
+                save_data.level_mut(&next_level.filename).unlocked = true;
             }
-        }) {
-            level_progress.num_levels_available = index + 2;
         } else {
             error!(
                 "Unable to find level {:?}, starting anew",
                 completed_up_to_level
             );
-            level_progress.num_levels_available = 1;
         }
-    } else {
-        level_progress.num_levels_available = 1;
     }
+    if let Ok(bests) = pkv.get::<HashMap<String, PersonalBest>>(LEGACY_LEVEL_STATS_PKV_KEY) {
+        for (filename, best) in bests {
+            save_data.level_mut(&filename).best = Some(best);
+        }
+    }
+    save_data
 }
 
+fn copy_profile_save_data(mut reader: EventReader<ProfileCopied>, mut pkv: ResMut<PkvStore>) {
+    for ProfileCopied { from, to } in reader.iter() {
+        let save_data = some_or!(pkv.get::<SaveData>(&profile_pkv_key(*from, SAVE_DATA_PKV_KEY)).ok(); continue);
+        if let Err(err) = pkv.set(&profile_pkv_key(*to, SAVE_DATA_PKV_KEY), &save_data) {
+            error!("Cannot copy level progression: {}", err);
+        }
+    }
+}
+
+/// The store cannot remove keys, so the deleted profile's data is overwritten with empty data
+/// instead of being kept around.
+fn clear_deleted_profile_save_data(
+    mut reader: EventReader<ProfileDeleted>,
+    mut pkv: ResMut<PkvStore>,
+) {
+    for ProfileDeleted { id } in reader.iter() {
+        if let Err(err) = pkv.set(
+            &profile_pkv_key(*id, SAVE_DATA_PKV_KEY),
+            &SaveData::default(),
+        ) {
+            error!("Cannot clear level progression: {}", err);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn handle_level_completion(
-    asset_server: Res<AssetServer>,
+    game_assets: Res<GameAssets>,
+    level_packs_assets: Res<Assets<LevelPacks>>,
     level_index_assets: Res<Assets<YoleckLevelIndex>>,
     mut pkv: ResMut<PkvStore>,
+    mut save_data: ResMut<SaveData>,
+    active_profile: Res<ActiveProfile>,
     mut level_progress: ResMut<LevelProgress>,
     mut state: ResMut<State<AppState>>,
 ) {
     let completed_level = some_or!(
-        level_progress.current_level.as_ref();
+        level_progress.current_level.clone();
         return // level completed inside editor
     );
-    let level_index =
-        some_or!(level_index_assets.get(&asset_server.load("levels/index.yoli")); return);
-    let mut it = level_index.iter();
-
-    let is_new_level_better = if let Ok(best_completed) = pkv.get::<String>(LEVEL_PKV_KEY) {
-        level_index
-            .iter()
-            .rev()
-            .find_map(|level| {
-                if level.filename == best_completed {
-                    Some(false)
-                } else if level.filename == *completed_level {
-                    Some(true)
-                } else {
-                    None
-                }
-            })
-            .unwrap_or(true)
+    if is_custom_level(&completed_level) {
+        level_progress.just_completed = level_progress.current_level.take();
+        state
+            .set(AppState::Menu(MenuState::LevelCompleted))
+            .unwrap();
+        return;
+    }
+    let level_packs = some_or!(level_packs_assets.get(&game_assets.level_packs); return);
+    let unlocked_levels = if let Some((pack, level_index)) =
+        level_packs.find_level(&level_index_assets, &completed_level)
+    {
+        pack.unlocked_by(level_index, &completed_level)
     } else {
-        true
+        // Level is not part of any pack - treat it as standalone
+        Vec::new()
     };
 
-    if is_new_level_better {
-        if let Err(err) = pkv.set(LEVEL_PKV_KEY, completed_level) {
-            error!("Cannot save level progression: {}", err);
+    save_data.level_mut(&completed_level).completed = true;
+    let mut next_level = None;
+    for unlocked_level in unlocked_levels.iter() {
+        let level_record = save_data.level_mut(unlocked_level);
+        level_record.unlocked = true;
+        if next_level.is_none() && !level_record.completed {
+            next_level = Some(unlocked_level.clone());
         }
     }
-    let _current_level = it
-        .by_ref()
-        .find(|level| level.filename == *completed_level)
-        .expect("Current level must be in the index");
+    let next_level = next_level.or_else(|| unlocked_levels.first().cloned());
+    save_data.save(&mut pkv, &active_profile);
+
     level_progress.just_completed = level_progress.current_level.take();
-    level_progress.current_level = it.next().map(|level| level.filename.clone());
-    level_progress.num_levels_available = 0;
+    level_progress.current_level = next_level;
     state
         .set(AppState::Menu(MenuState::LevelCompleted))
         .unwrap();
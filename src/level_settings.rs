@@ -0,0 +1,124 @@
+use bevy::prelude::*;
+use bevy_yoleck::{egui, YoleckEdit, YoleckExtForApp, YoleckPopulate, YoleckTypeHandler};
+use serde::{Deserialize, Serialize};
+
+use crate::camera::{CameraMode, LevelCameraMode};
+use crate::level_stats::RunStats;
+
+pub struct LevelSettingsPlugin;
+
+impl Plugin for LevelSettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_yoleck_handler({
+            YoleckTypeHandler::<LevelSettings>::new("LevelSettings")
+                .populate_with(populate)
+                .with(crate::yoleck_utils::position_adapter(
+                    |level_settings: &mut LevelSettings| (&mut level_settings.position, IVec2::ONE),
+                    0.0,
+                ))
+                .edit_with(edit)
+        });
+    }
+}
+
+/// Level-wide properties. Only one such entity is expected in each level - its position only
+/// matters for picking it in the editor.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct LevelSettings {
+    #[serde(default)]
+    position: Vec2,
+    /// In seconds. Zero means there is no par time.
+    #[serde(default)]
+    par_time: f32,
+    /// Number of pickups and throws. Zero means there is no par.
+    #[serde(default)]
+    par_moves: u32,
+    #[serde(default)]
+    camera_mode: CameraMode,
+    /// Filename under `assets/music`. Empty for no music.
+    #[serde(default = "default_music")]
+    music: String,
+}
+
+pub const DEFAULT_MUSIC: &str = "default.wav";
+
+fn default_music() -> String {
+    DEFAULT_MUSIC.to_owned()
+}
+
+/// Levels without it play [`DEFAULT_MUSIC`].
+#[derive(Component)]
+pub struct LevelMusic(pub String);
+
+#[derive(Component)]
+pub struct LevelPar {
+    pub time: f32,
+    pub moves: u32,
+}
+
+impl LevelPar {
+    /// One star for finishing the level, and one for each par met.
+    pub fn stars_for(&self, run: &RunStats) -> u8 {
+        let mut stars = 1;
+        if self.time <= 0.0 || run.time <= self.time {
+            stars += 1;
+        }
+        if self.moves == 0 || run.pickups + run.throws <= self.moves {
+            stars += 1;
+        }
+        stars
+    }
+}
+
+fn populate(mut populate: YoleckPopulate<LevelSettings>) {
+    populate.populate(|ctx, data, mut cmd| {
+        cmd.insert(LevelPar {
+            time: data.par_time,
+            moves: data.par_moves,
+        });
+        cmd.insert(LevelCameraMode(data.camera_mode));
+        cmd.insert(LevelMusic(data.music.clone()));
+        cmd.insert_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: Color::GREEN.clone().set_a(0.5).to_owned(),
+                custom_size: Some(Vec2::new(1.0, 1.0)),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        cmd.insert(Visibility {
+            is_visible: ctx.is_in_editor(),
+        });
+    });
+}
+
+fn edit(mut edit: YoleckEdit<LevelSettings>) {
+    edit.edit(|_ctx, data, ui| {
+        ui.add(
+            egui::DragValue::new(&mut data.par_time)
+                .prefix("Par Time:")
+                .suffix("s")
+                .clamp_range(0.0..=3600.0)
+                .speed(0.5),
+        );
+        ui.add(
+            egui::DragValue::new(&mut data.par_moves)
+                .prefix("Par Moves:")
+                .clamp_range(0..=1000)
+                .speed(0.1),
+        );
+        ui.horizontal(|ui| {
+            ui.label("Camera:");
+            ui.selectable_value(&mut data.camera_mode, CameraMode::FitLevel, "Fit Level");
+            ui.selectable_value(
+                &mut data.camera_mode,
+                CameraMode::FollowPlayer,
+                "Follow Player",
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Music:");
+            ui.text_edit_singleline(&mut data.music);
+        });
+    });
+}
@@ -0,0 +1,168 @@
+use bevy::prelude::*;
+use bevy_pkv::PkvStore;
+use serde::{Deserialize, Serialize};
+
+use crate::custom_levels::is_custom_level;
+use crate::global_types::{AppState, LevelProgress};
+use crate::laser::TriggerLaserShot;
+use crate::level_progress::SaveData;
+use crate::level_settings::LevelPar;
+use crate::player_control::PlayerAction;
+use crate::profile::ActiveProfile;
+use crate::utils::some_or;
+
+pub struct LevelStatsPlugin;
+
+impl Plugin for LevelStatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CurrentRunStats>();
+        app.add_system_set(SystemSet::on_enter(AppState::LoadLevel).with_system(
+            |mut current_run_stats: ResMut<CurrentRunStats>| {
+                current_run_stats.stats = Default::default();
+            },
+        ));
+        app.add_system_set(SystemSet::on_update(AppState::Game).with_system(update_run_stats));
+        app.add_system_set(
+            SystemSet::on_enter(AppState::LevelCompleted).with_system(record_completed_run),
+        );
+    }
+}
+
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct RunStats {
+    pub time: f32,
+    pub jumps: u32,
+    pub pickups: u32,
+    pub throws: u32,
+    pub laser_shots: u32,
+}
+
+impl RunStats {
+    pub fn summary(&self) -> String {
+        format!(
+            "Time: {}   Jumps: {}   Pickups: {}   Throws: {}   Laser Shots: {}",
+            format_time(self.time),
+            self.jumps,
+            self.pickups,
+            self.throws,
+            self.laser_shots,
+        )
+    }
+}
+
+/// Each field holds the best value ever achieved for it, not necessarily in the same run.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PersonalBest {
+    pub time: f32,
+    pub jumps: u32,
+    pub pickups: u32,
+    pub throws: u32,
+    pub laser_shots: u32,
+    /// `None` for levels without par values.
+    #[serde(default)]
+    pub stars: Option<u8>,
+}
+
+impl PersonalBest {
+    fn new(run: &RunStats, stars: Option<u8>) -> Self {
+        Self {
+            time: run.time,
+            jumps: run.jumps,
+            pickups: run.pickups,
+            throws: run.throws,
+            laser_shots: run.laser_shots,
+            stars,
+        }
+    }
+
+    fn improve_with(&mut self, run: &RunStats, stars: Option<u8>) -> bool {
+        let is_new_best_time = run.time < self.time;
+        self.stars = self.stars.max(stars);
+        self.time = self.time.min(run.time);
+        self.jumps = self.jumps.min(run.jumps);
+        self.pickups = self.pickups.min(run.pickups);
+        self.throws = self.throws.min(run.throws);
+        self.laser_shots = self.laser_shots.min(run.laser_shots);
+        is_new_best_time
+    }
+}
+
+pub struct CompletedRun {
+    pub stats: RunStats,
+    pub is_new_best_time: bool,
+    pub stars: Option<u8>,
+}
+
+#[derive(Default)]
+pub struct CurrentRunStats {
+    pub stats: RunStats,
+    pub just_completed: Option<CompletedRun>,
+}
+
+pub fn format_stars(stars: u8) -> String {
+    (0..3).map(|i| if i < stars { '★' } else { '☆' }).collect()
+}
+
+pub fn format_time(seconds: f32) -> String {
+    let minutes = (seconds / 60.0).floor();
+    format!("{}:{:05.2}", minutes, seconds - 60.0 * minutes)
+}
+
+fn update_run_stats(
+    time: Res<Time>,
+    mut current_run_stats: ResMut<CurrentRunStats>,
+    mut player_action_reader: EventReader<PlayerAction>,
+    mut laser_shot_reader: EventReader<TriggerLaserShot>,
+) {
+    let stats = &mut current_run_stats.stats;
+    stats.time += time.delta_seconds();
+    for player_action in player_action_reader.iter() {
+        match player_action {
+            PlayerAction::Jump => stats.jumps += 1,
+            PlayerAction::Pickup => stats.pickups += 1,
+            PlayerAction::Throw => stats.throws += 1,
+        }
+    }
+    stats.laser_shots += laser_shot_reader.iter().count() as u32;
+}
+
+fn record_completed_run(
+    level_progress: Res<LevelProgress>,
+    level_par_query: Query<&LevelPar>,
+    mut current_run_stats: ResMut<CurrentRunStats>,
+    mut save_data: ResMut<SaveData>,
+    active_profile: Res<ActiveProfile>,
+    mut pkv: ResMut<PkvStore>,
+) {
+    let completed_level = some_or!(
+        level_progress.current_level.as_ref();
+        return // level completed inside editor
+    );
+    let run = current_run_stats.stats.clone();
+    // Levels without par values don't award stars
+    let stars = level_par_query
+        .get_single()
+        .ok()
+        .map(|level_par| level_par.stars_for(&run));
+    if is_custom_level(completed_level) {
+        current_run_stats.just_completed = Some(CompletedRun {
+            stats: run,
+            is_new_best_time: false,
+            stars,
+        });
+        return;
+    }
+    let level_record = save_data.level_mut(completed_level);
+    let is_new_best_time = if let Some(best) = level_record.best.as_mut() {
+        best.improve_with(&run, stars)
+    } else {
+        level_record.best = Some(PersonalBest::new(&run, stars));
+        true
+    };
+    save_data.save(&mut pkv, &active_profile);
+    current_run_stats.just_completed = Some(CompletedRun {
+        stats: run,
+        is_new_best_time,
+        stars,
+    });
+}
@@ -1,71 +1,132 @@
+mod animation;
 mod camera;
+mod camera_zone;
+mod checkpoint;
+mod controls;
+mod custom_levels;
 mod door;
 mod door_key;
+mod drop_preview;
 mod floating_text;
 mod global_types;
+mod hazard;
+mod hud;
 mod laser;
+mod level_packs;
 mod level_progress;
+mod level_settings;
+mod level_stats;
 mod loading;
 mod menu;
 mod part_activation;
 mod part_behavior;
+mod particles;
 mod parts_manipulation;
 mod physics_utils;
 mod player;
 mod player_control;
+mod profile;
+mod quick_save;
+mod rewind;
 mod robot_part;
+mod settings;
+mod sound;
 mod utils;
 mod wall;
+mod world_snapshot;
 mod yoleck_utils;
 
+use std::path::PathBuf;
+
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::RapierConfiguration;
 use bevy_yoleck::{YoleckLoadingCommand, YoleckManaged, YoleckSyncWithEditorState};
 
+use self::animation::AnimationPlugin;
 use self::camera::CameraPlugin;
+use self::camera_zone::CameraZonePlugin;
+use self::checkpoint::CheckpointPlugin;
+use self::custom_levels::{is_custom_level, CustomLevelsPlugin};
 use self::door::DoorPlugin;
 use self::door_key::DoorKeyPlugin;
+use self::drop_preview::DropPreviewPlugin;
 use self::floating_text::FloatingTextPlugin;
 use self::global_types::{AppState, LevelProgress, MenuState};
+use self::hazard::HazardPlugin;
+use self::hud::HudPlugin;
 use self::laser::LaserPlugin;
+use self::level_packs::LevelPacksPlugin;
 use self::level_progress::LevelProgressPlugin;
+use self::level_settings::LevelSettingsPlugin;
+use self::level_stats::LevelStatsPlugin;
 use self::loading::LoadingPlugin;
 use self::menu::MenuPlugin;
 use self::part_activation::PartActivationPlugin;
 use self::part_behavior::PartBehaviorPlugin;
+use self::particles::ParticlesPlugin;
 use self::parts_manipulation::PartsManipulationPlugin;
 use self::player::PlayerPlugin;
 use self::player_control::PlayerControlPlugin;
+use self::profile::ProfilePlugin;
+use self::quick_save::QuickSavePlugin;
+use self::rewind::{RewindPlugin, RewindState};
 use self::robot_part::RobotPartPlugin;
+use self::settings::SettingsPlugin;
+use self::sound::SoundPlugin;
 use self::wall::WallPlugin;
+use self::world_snapshot::WorldSnapshotPlugin;
 
 pub use self::global_types::MenuActionForKbgp;
 
 pub struct GamePlugin {
     pub is_editor: bool,
     pub start_at_level: Option<String>,
+    pub custom_levels_dir: Option<PathBuf>,
 }
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
+        // Must come before the loading plugin, which loads their asset files
+        app.add_plugin(LevelPacksPlugin);
+        app.add_plugin(AnimationPlugin);
         app.add_plugin(LoadingPlugin);
         app.add_plugin(CameraPlugin {
             is_editor: self.is_editor,
         });
+        app.add_plugin(ProfilePlugin);
+        app.add_plugin(SettingsPlugin {
+            is_editor: self.is_editor,
+        });
+        app.add_plugin(CustomLevelsPlugin {
+            levels_dir: self.custom_levels_dir.clone(),
+        });
         app.add_plugin(LevelProgressPlugin);
+        app.add_plugin(LevelStatsPlugin);
+        app.add_plugin(SoundPlugin {
+            is_editor: self.is_editor,
+        });
 
         app.add_plugin(FloatingTextPlugin);
+        app.add_plugin(LevelSettingsPlugin);
+        app.add_plugin(CameraZonePlugin);
         app.add_plugin(PlayerPlugin);
         app.add_plugin(WallPlugin);
         app.add_plugin(RobotPartPlugin);
         app.add_plugin(DoorKeyPlugin);
         app.add_plugin(DoorPlugin);
+        app.add_plugin(HazardPlugin);
+        app.add_plugin(CheckpointPlugin);
 
         app.add_plugin(PlayerControlPlugin);
         app.add_plugin(PartsManipulationPlugin);
+        app.add_plugin(DropPreviewPlugin);
         app.add_plugin(PartBehaviorPlugin);
         app.add_plugin(LaserPlugin);
+        app.add_plugin(ParticlesPlugin);
         app.add_plugin(PartActivationPlugin);
+        app.add_plugin(WorldSnapshotPlugin);
+        app.add_plugin(RewindPlugin);
+        app.add_plugin(QuickSavePlugin);
 
         app.add_system(enable_disable_physics);
         if self.is_editor {
@@ -75,7 +136,8 @@ impl Plugin for GamePlugin {
             });
         } else {
             app.add_plugin(MenuPlugin);
-            app.add_state(AppState::Menu(MenuState::Main));
+            app.add_plugin(HudPlugin);
+            app.add_state(AppState::Menu(MenuState::ProfileSelect));
             app.add_system_set(
                 SystemSet::on_enter(AppState::LoadLevel).with_system(handle_level_loading),
             );
@@ -95,9 +157,11 @@ impl Plugin for GamePlugin {
 
 fn enable_disable_physics(
     state: Res<State<AppState>>,
+    rewind_state: Res<RewindState>,
     mut rapier_configuration: ResMut<RapierConfiguration>,
 ) {
-    rapier_configuration.physics_pipeline_active = *state.current() == AppState::Game;
+    rapier_configuration.physics_pipeline_active =
+        *state.current() == AppState::Game && !rewind_state.is_rewinding();
 }
 
 fn handle_level_loading(
@@ -115,7 +179,11 @@ fn handle_level_loading(
         .current_level
         .as_ref()
         .expect("Entered LoadLevel state when current_level is None");
-    *yoleck_loading_command =
-        YoleckLoadingCommand::FromAsset(asset_server.load(&format!("levels/{}", current_level)));
+    let level_handle = if is_custom_level(current_level) {
+        asset_server.load(current_level.as_str())
+    } else {
+        asset_server.load(&format!("levels/{}", current_level))
+    };
+    *yoleck_loading_command = YoleckLoadingCommand::FromAsset(level_handle);
     state.overwrite_set(AppState::Game).unwrap();
 }
@@ -2,6 +2,9 @@ use bevy::prelude::*;
 use bevy_asset_loader::prelude::{AssetCollection, AssetCollectionApp};
 use bevy_yoleck::YoleckLevelIndex;
 
+use crate::animation::AnimationClips;
+use crate::level_packs::LevelPacks;
+
 pub struct LoadingPlugin;
 
 impl Plugin for LoadingPlugin {
@@ -16,6 +19,9 @@ pub struct GameAssets {
     #[asset(path = "sprites/player.png")]
     pub player: Handle<TextureAtlas>,
 
+    #[asset(path = "sprites/player.anim.json")]
+    pub player_animations: Handle<AnimationClips>,
+
     #[asset(texture_atlas(tile_size_x = 64.0, tile_size_y = 64.0, columns = 1, rows = 1))]
     #[asset(path = "sprites/platform.png")]
     pub platform: Handle<TextureAtlas>,
@@ -24,18 +30,30 @@ pub struct GameAssets {
     #[asset(path = "sprites/hover.png")]
     pub hover: Handle<TextureAtlas>,
 
+    #[asset(path = "sprites/hover.anim.json")]
+    pub hover_animations: Handle<AnimationClips>,
+
     #[asset(texture_atlas(tile_size_x = 64.0, tile_size_y = 64.0, columns = 1, rows = 2))]
     #[asset(path = "sprites/laser.png")]
     pub laser: Handle<TextureAtlas>,
 
+    #[asset(path = "sprites/laser.anim.json")]
+    pub laser_animations: Handle<AnimationClips>,
+
     #[asset(texture_atlas(tile_size_x = 64.0, tile_size_y = 64.0, columns = 1, rows = 2))]
     #[asset(path = "sprites/stationary.png")]
     pub stationary: Handle<TextureAtlas>,
 
+    #[asset(path = "sprites/stationary.anim.json")]
+    pub stationary_animations: Handle<AnimationClips>,
+
     #[asset(texture_atlas(tile_size_x = 64.0, tile_size_y = 64.0, columns = 1, rows = 2))]
     #[asset(path = "sprites/rotator.png")]
     pub rotator: Handle<TextureAtlas>,
 
+    #[asset(path = "sprites/rotator.anim.json")]
+    pub rotator_animations: Handle<AnimationClips>,
+
     #[asset(path = "sprites/door_key.png")]
     pub door_key: Handle<Image>,
 
@@ -43,9 +61,42 @@ pub struct GameAssets {
     #[asset(path = "sprites/door.png")]
     pub door: Handle<TextureAtlas>,
 
+    #[asset(path = "sounds/jump.wav")]
+    pub jump_sound: Handle<AudioSource>,
+
+    #[asset(path = "sounds/pickup.wav")]
+    pub pickup_sound: Handle<AudioSource>,
+
+    #[asset(path = "sounds/drop.wav")]
+    pub drop_sound: Handle<AudioSource>,
+
+    #[asset(path = "sounds/mount.wav")]
+    pub mount_sound: Handle<AudioSource>,
+
+    #[asset(path = "sounds/laser_fire.wav")]
+    pub laser_fire_sound: Handle<AudioSource>,
+
+    #[asset(path = "sounds/wall_hit.wav")]
+    pub wall_hit_sound: Handle<AudioSource>,
+
+    #[asset(path = "sounds/wall_break.wav")]
+    pub wall_break_sound: Handle<AudioSource>,
+
+    #[asset(path = "sounds/key_collected.wav")]
+    pub key_collected_sound: Handle<AudioSource>,
+
+    #[asset(path = "sounds/door_open.wav")]
+    pub door_open_sound: Handle<AudioSource>,
+
+    #[asset(path = "sounds/level_complete.wav")]
+    pub level_complete_sound: Handle<AudioSource>,
+
     #[asset(path = "fonts/FiraSans-Bold.ttf")]
     pub font: Handle<Font>,
 
     #[asset(path = "levels/index.yoli")]
     pub level_index: Handle<YoleckLevelIndex>,
+
+    #[asset(path = "levels/world.packs.json")]
+    pub level_packs: Handle<LevelPacks>,
 }
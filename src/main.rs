@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use bevy::prelude::*;
 use bevy_egui_kbgp::{KbgpNavBindings, KbgpNavCommand, KbgpPlugin, KbgpSettings};
 use bevy_pkv::PkvStore;
@@ -10,6 +12,9 @@ struct Args {
     editor: bool,
     #[clap(long)]
     level: Option<String>,
+    /// Directory with custom `.yol` files to list in the level select menu.
+    #[clap(long)]
+    levels_dir: Option<PathBuf>,
 }
 
 fn main() {
@@ -30,10 +35,6 @@ fn main() {
         app.add_plugin(bevy_yoleck::vpeol_2d::YoleckVpeol2dPlugin);
     } else {
         app.add_plugin(bevy_yoleck::YoleckPluginForGame);
-        app.insert_resource(bevy_egui::EguiSettings {
-            scale_factor: 2.0,
-            default_open_url_target: None,
-        });
         app.add_plugin(KbgpPlugin);
         app.insert_resource(KbgpSettings {
             disable_default_navigation: true,
@@ -59,6 +60,7 @@ fn main() {
     app.add_plugin(GamePlugin {
         is_editor: args.editor,
         start_at_level: args.level,
+        custom_levels_dir: args.levels_dir,
     });
     app.add_plugin(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(1.0));
     app.insert_resource(RapierConfiguration {
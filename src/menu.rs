@@ -2,11 +2,24 @@ use bevy::prelude::*;
 use bevy_egui_kbgp::bevy_egui::EguiContext;
 use bevy_egui_kbgp::egui;
 use bevy_egui_kbgp::prelude::*;
+use bevy_pkv::PkvStore;
 use bevy_yoleck::YoleckLevelIndex;
 
+use crate::checkpoint::{CheckpointState, RespawnAtCheckpoint};
+use crate::controls::{Control, RESERVED_GAMEPAD_BUTTONS, RESERVED_KEYS};
+use crate::custom_levels::{is_custom_level, CustomLevels};
 use crate::global_types::LevelProgress;
 use crate::global_types::{AppState, MenuState};
+use crate::level_packs::LevelPacks;
+use crate::level_progress::SaveData;
+use crate::level_stats::{format_stars, format_time, CurrentRunStats};
 use crate::loading::GameAssets;
+use crate::profile::{ActiveProfile, ProfileCopied, ProfileDeleted, Profiles};
+use crate::quick_save::{QuickLoadRequest, QuickSaveRequest, QuickSaveSlot};
+use crate::settings::{
+    next_option, GameSettings, WindowModeSetting, MAX_PLAYERS, RESOLUTIONS, STICK_DEADZONES,
+    UI_SCALES, VOLUMES,
+};
 use crate::utils::some_or;
 use crate::MenuActionForKbgp;
 
@@ -15,6 +28,10 @@ pub struct MenuPlugin;
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
         app.add_system(pause_unpause_game);
+        app.add_system_set(
+            SystemSet::on_update(AppState::Menu(MenuState::ProfileSelect))
+                .with_system(profile_select_menu),
+        );
         app.add_system_set(
             SystemSet::on_update(AppState::Menu(MenuState::Main)).with_system(main_menu),
         );
@@ -22,6 +39,12 @@ impl Plugin for MenuPlugin {
             SystemSet::on_update(AppState::Menu(MenuState::LevelSelect))
                 .with_system(level_select_menu),
         );
+        app.add_system_set(
+            SystemSet::on_update(AppState::Menu(MenuState::Settings)).with_system(settings_menu),
+        );
+        app.add_system_set(
+            SystemSet::on_update(AppState::Menu(MenuState::Controls)).with_system(controls_menu),
+        );
         app.add_system_set(
             SystemSet::on_update(AppState::Menu(MenuState::Pause)).with_system(pause_menu),
         );
@@ -57,6 +80,8 @@ enum FocusLabel {
     Start,
     NextLevel,
     CurrentLevel,
+    ActiveProfile,
+    NewProfile,
     BackToMainMenu,
     Exit,
 }
@@ -73,22 +98,189 @@ fn menu_layout(egui_context: &egui::Context, dlg: impl FnOnce(&mut egui::Ui)) {
         });
 }
 
-fn format_level_name(filename: &str) -> String {
+pub fn format_level_name(level: &str) -> String {
+    // Custom levels are full paths
+    let filename = std::path::Path::new(level)
+        .file_name()
+        .and_then(|filename| filename.to_str())
+        .unwrap_or(level);
     filename
         .strip_suffix(".yol")
         .unwrap_or(filename)
         .replace('_', " ")
 }
 
+#[derive(Default)]
+enum ProfileMenuMode {
+    #[default]
+    Select,
+    Rename {
+        profile_id: u32,
+        name: String,
+    },
+    ConfirmDelete {
+        profile_id: u32,
+    },
+}
+
+#[allow(clippy::too_many_arguments)]
+fn profile_select_menu(
+    mut egui_context: ResMut<EguiContext>,
+    mut state: ResMut<State<AppState>>,
+    mut profiles: ResMut<Profiles>,
+    mut active_profile: ResMut<ActiveProfile>,
+    mut profile_copied_writer: EventWriter<ProfileCopied>,
+    mut profile_deleted_writer: EventWriter<ProfileDeleted>,
+    mut pkv: ResMut<PkvStore>,
+    mut mode: Local<ProfileMenuMode>,
+    #[cfg(not(target_arch = "wasm32"))] mut exit: EventWriter<bevy::app::AppExit>,
+) {
+    menu_layout(egui_context.ctx_mut(), |ui| {
+        if ui.kbgp_user_action() == Some(MenuActionForKbgp) {
+            if let ProfileMenuMode::Select = *mode {
+                ui.kbgp_set_focus_label(FocusLabel::Exit);
+            } else {
+                *mode = ProfileMenuMode::Select;
+                ui.kbgp_set_focus_label(FocusLabel::ActiveProfile);
+            }
+        }
+        ui.label(
+            egui::RichText::new("Select Profile")
+                .color(egui::Color32::WHITE)
+                .background_color(egui::Color32::BLACK),
+        );
+        let mut profiles_changed = false;
+        egui::Grid::new("profiles").show(ui, |ui| {
+            for profile in profiles.profiles.clone() {
+                let is_last_active = profiles.last_active == Some(profile.id);
+                match &mut *mode {
+                    ProfileMenuMode::Rename { profile_id, name } if *profile_id == profile.id => {
+                        let response = ui.text_edit_singleline(name);
+                        response.request_focus();
+                        let confirmed = ui.button("OK").kbgp_navigation().clicked()
+                            || (response.lost_focus() && ui.input().key_pressed(egui::Key::Enter));
+                        if confirmed && !name.trim().is_empty() {
+                            profiles.rename(profile.id, name.trim().to_owned());
+                            profiles_changed = true;
+                            *mode = ProfileMenuMode::Select;
+                            ui.kbgp_set_focus_label(FocusLabel::ActiveProfile);
+                        }
+                    }
+                    _ => {
+                        let mut response = ui.button(&profile.name).kbgp_navigation();
+                        if is_last_active {
+                            response = response
+                                .kbgp_initial_focus()
+                                .kbgp_focus_label(FocusLabel::ActiveProfile);
+                        }
+                        if response.clicked() {
+                            active_profile.0 = Some(profile.id);
+                            profiles.last_active = Some(profile.id);
+                            profiles_changed = true;
+                            *mode = ProfileMenuMode::Select;
+                            state.set(AppState::Menu(MenuState::Main)).unwrap();
+                            ui.kbgp_clear_input();
+                        }
+                        if ui.button("Rename").kbgp_navigation().clicked() {
+                            *mode = ProfileMenuMode::Rename {
+                                profile_id: profile.id,
+                                name: profile.name.clone(),
+                            };
+                        }
+                    }
+                }
+                if ui.button("Copy").kbgp_navigation().clicked() {
+                    if let Some(new_profile_id) = profiles.copy(profile.id) {
+                        profile_copied_writer.send(ProfileCopied {
+                            from: profile.id,
+                            to: new_profile_id,
+                        });
+                        profiles_changed = true;
+                    }
+                }
+                match *mode {
+                    ProfileMenuMode::ConfirmDelete { profile_id } if profile_id == profile.id => {
+                        if ui
+                            .button(egui::RichText::new("Really Delete?").color(egui::Color32::RED))
+                            .kbgp_navigation()
+                            .clicked()
+                        {
+                            profiles.delete(profile.id);
+                            profile_deleted_writer.send(ProfileDeleted { id: profile.id });
+                            if active_profile.0 == Some(profile.id) {
+                                active_profile.0 = None;
+                            }
+                            profiles_changed = true;
+                            *mode = ProfileMenuMode::Select;
+                            ui.kbgp_set_focus_label(FocusLabel::NewProfile);
+                        }
+                    }
+                    _ => {
+                        if ui.button("Delete").kbgp_navigation().clicked() {
+                            *mode = ProfileMenuMode::ConfirmDelete {
+                                profile_id: profile.id,
+                            };
+                        }
+                    }
+                }
+                ui.end_row();
+            }
+        });
+        let mut response = ui
+            .button("New Profile")
+            .kbgp_navigation()
+            .kbgp_focus_label(FocusLabel::NewProfile);
+        if profiles.profiles.is_empty() {
+            response = response.kbgp_initial_focus();
+        }
+        if response.clicked() {
+            let name = format!("Player {}", profiles.profiles.len() + 1);
+            let profile_id = profiles.create(name.clone());
+            profiles_changed = true;
+            *mode = ProfileMenuMode::Rename { profile_id, name };
+        }
+        if profiles_changed {
+            profiles.save(&mut pkv);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if ui
+            .button("Exit")
+            .kbgp_navigation()
+            .kbgp_focus_label(FocusLabel::Exit)
+            .clicked()
+        {
+            exit.send(bevy::app::AppExit);
+        }
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
 fn main_menu(
     mut egui_context: ResMut<EguiContext>,
     mut state: ResMut<State<AppState>>,
+    profiles: Res<Profiles>,
+    active_profile: Res<ActiveProfile>,
+    mut settings: ResMut<GameSettings>,
+    mut pkv: ResMut<PkvStore>,
+    quick_save_slot: Res<QuickSaveSlot>,
+    mut quick_load_writer: EventWriter<QuickLoadRequest>,
     #[cfg(not(target_arch = "wasm32"))] mut exit: EventWriter<bevy::app::AppExit>,
 ) {
     menu_layout(egui_context.ctx_mut(), |ui| {
         if ui.kbgp_user_action() == Some(MenuActionForKbgp) {
             ui.kbgp_set_focus_label(FocusLabel::Exit);
         }
+        if let Some(saved_level) = quick_save_slot.saved_level() {
+            if ui
+                .button(format!("Continue: {}", format_level_name(saved_level)))
+                .kbgp_navigation()
+                .kbgp_initial_focus()
+                .clicked()
+            {
+                quick_load_writer.send(QuickLoadRequest);
+                ui.kbgp_clear_input();
+            }
+        }
         if ui
             .button("Start")
             .kbgp_navigation()
@@ -100,6 +292,31 @@ fn main_menu(
             ui.kbgp_clear_input();
             ui.kbgp_set_focus_label(FocusLabel::NextLevel);
         }
+        if ui
+            .button(format!("Players: {}", settings.num_players))
+            .kbgp_navigation()
+            .clicked()
+        {
+            settings.num_players = settings.num_players % MAX_PLAYERS + 1;
+            settings.save(&mut pkv, &active_profile);
+        }
+        if ui.button("Settings").kbgp_navigation().clicked() {
+            state.push(AppState::Menu(MenuState::Settings)).unwrap();
+            ui.kbgp_clear_input();
+        }
+        let profile_name = active_profile
+            .0
+            .and_then(|profile_id| profiles.get(profile_id))
+            .map(|profile| profile.name.as_str())
+            .unwrap_or("none");
+        if ui
+            .button(format!("Profile: {}", profile_name))
+            .kbgp_navigation()
+            .clicked()
+        {
+            state.set(AppState::Menu(MenuState::ProfileSelect)).unwrap();
+            ui.kbgp_clear_input();
+        }
         #[cfg(not(target_arch = "wasm32"))]
         if ui
             .button("Exit")
@@ -112,13 +329,40 @@ fn main_menu(
     });
 }
 
+#[allow(clippy::too_many_arguments)]
 fn level_select_menu(
     mut egui_context: ResMut<EguiContext>,
     mut state: ResMut<State<AppState>>,
     mut level_progress: ResMut<LevelProgress>,
     game_assets: Res<GameAssets>,
+    level_packs_assets: Res<Assets<LevelPacks>>,
     level_index_assets: Res<Assets<YoleckLevelIndex>>,
+    save_data: Res<SaveData>,
+    mut custom_levels: ResMut<CustomLevels>,
+    mut pkv: ResMut<PkvStore>,
+    mut selected_pack: Local<Option<usize>>,
+    mut custom_levels_dir_text: Local<Option<String>>,
 ) {
+    let level_packs = level_packs_assets.get(&game_assets.level_packs);
+    // The custom levels section comes after all the packs
+    let custom_levels_section_index = level_packs.map(|level_packs| level_packs.0.len());
+    let selected_pack_index = *selected_pack.get_or_insert_with(|| {
+        let current_level = some_or!(level_progress.current_level.as_ref(); return 0);
+        if is_custom_level(current_level) {
+            return custom_levels_section_index.unwrap_or(0);
+        }
+        level_packs
+            .and_then(|level_packs| {
+                let (pack, _) = level_packs.find_level(&level_index_assets, current_level)?;
+                level_packs
+                    .0
+                    .iter()
+                    .position(|other| std::ptr::eq(other, pack))
+            })
+            .unwrap_or(0)
+    });
+    let pack = level_packs.and_then(|level_packs| level_packs.0.get(selected_pack_index));
+    let level_index = pack.and_then(|pack| level_index_assets.get(&pack.index_handle));
     menu_layout(egui_context.ctx_mut(), |ui| {
         if ui.kbgp_user_action() == Some(MenuActionForKbgp) {
             ui.kbgp_set_focus_label(FocusLabel::BackToMainMenu);
@@ -127,27 +371,170 @@ fn level_select_menu(
             .button("Back To Menu")
             .kbgp_navigation()
             .kbgp_focus_label(FocusLabel::BackToMainMenu);
-        let level_index = level_index_assets.get(&game_assets.level_index);
-        if level_index
-            .map(|level_index| level_index.len() < level_progress.num_levels_available)
-            .unwrap_or(true)
-        {
+        // The first level in the pack the player can play but did not finish yet, if any.
+        let next_level_index = pack.zip(level_index).and_then(|(pack, level_index)| {
+            level_index
+                .iter()
+                .enumerate()
+                .filter(|(_, level)| pack.bonus_level_star_requirement(&level.filename).is_none())
+                .find(|(index, level)| {
+                    save_data.is_unlocked(pack, *index, &level.filename)
+                        && !save_data
+                            .level(&level.filename)
+                            .map(|level| level.completed)
+                            .unwrap_or(false)
+                })
+                .map(|(index, _)| index)
+        });
+        if next_level_index.is_none() {
             response = response.kbgp_focus_label(FocusLabel::NextLevel);
         }
         if response.clicked() {
             state.set(AppState::Menu(MenuState::Main)).unwrap();
             ui.kbgp_clear_input();
+            *selected_pack = None;
+        }
+        let total_stars = save_data.total_stars();
+        ui.label(
+            egui::RichText::new(format!("Stars: {}", total_stars))
+                .color(egui::Color32::GOLD)
+                .background_color(egui::Color32::BLACK),
+        );
+        if save_data.is_read_only() {
+            ui.label(
+                egui::RichText::new(
+                    "Saved by a newer version of the game - progress will not be saved",
+                )
+                .color(egui::Color32::RED)
+                .background_color(egui::Color32::BLACK),
+            );
+        }
+        let level_packs = some_or!(level_packs; return);
+        egui::Grid::new("level_packs").show(ui, |ui| {
+            for (pack_index, pack) in level_packs.0.iter().enumerate() {
+                let caption = if save_data.is_pack_unlocked(pack) {
+                    pack.name.clone()
+                } else {
+                    format!("{} (requires {} stars)", pack.name, pack.required_stars)
+                };
+                if ui
+                    .add(egui::SelectableLabel::new(
+                        pack_index == selected_pack_index,
+                        caption,
+                    ))
+                    .kbgp_navigation()
+                    .clicked()
+                {
+                    *selected_pack = Some(pack_index);
+                }
+            }
+            if ui
+                .add(egui::SelectableLabel::new(
+                    Some(selected_pack_index) == custom_levels_section_index,
+                    "Custom",
+                ))
+                .kbgp_navigation()
+                .clicked()
+            {
+                *selected_pack = custom_levels_section_index;
+            }
+        });
+        if Some(selected_pack_index) == custom_levels_section_index {
+            let dir_text = custom_levels_dir_text.get_or_insert_with(|| {
+                custom_levels
+                    .dir()
+                    .and_then(|dir| dir.to_str())
+                    .unwrap_or_default()
+                    .to_owned()
+            });
+            ui.horizontal(|ui| {
+                ui.label("Directory:");
+                ui.text_edit_singleline(dir_text);
+                if ui.button("Scan").kbgp_navigation().clicked() {
+                    custom_levels.set_dir(dir_text.into());
+                    custom_levels.save_dir(&mut pkv);
+                }
+            });
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for level in custom_levels.levels() {
+                    let mut response = ui.button(format_level_name(level)).kbgp_navigation();
+                    if Some(level) == level_progress.current_level.as_ref() {
+                        response = response.kbgp_focus_label(FocusLabel::CurrentLevel);
+                    }
+                    if response.clicked() {
+                        level_progress.current_level = Some(level.clone());
+                        state.set(AppState::LoadLevel).unwrap();
+                        *selected_pack = None;
+                    }
+                }
+            });
+            return;
         }
-        egui::ScrollArea::vertical().show(ui, |ui| {
-            let level_index = some_or!(level_index; return);
+        let (pack, level_index) = some_or!(pack.zip(level_index); return);
+        egui::ScrollArea::both().show(ui, |ui| {
+            let cell_size = egui::vec2(220.0, 60.0);
+            let node_positions = level_index
+                .iter()
+                .enumerate()
+                .map(|(index, level)| {
+                    let position = pack.map_position(index, &level.filename);
+                    egui::vec2(position.x, position.y)
+                })
+                .collect::<Vec<_>>();
+            let map_size = node_positions
+                .iter()
+                .fold(egui::Vec2::ZERO, |size, position| size.max(*position))
+                + egui::Vec2::splat(1.0);
+            let (map_rect, _) = ui.allocate_exact_size(map_size * cell_size, egui::Sense::hover());
+            let node_center = |index: usize| {
+                map_rect.min + (node_positions[index] + egui::Vec2::splat(0.5)) * cell_size
+            };
+
             for (index, level) in level_index.iter().enumerate() {
-                let mut response = ui
-                    .add_enabled(
-                        index < level_progress.num_levels_available,
-                        egui::Button::new(format_level_name(&level.filename)),
+                for unlocked_level in pack.unlocked_by(level_index, &level.filename) {
+                    let unlocked_index = some_or!(level_index
+                        .iter()
+                        .position(|level| level.filename == unlocked_level); continue);
+                    let color = if save_data.is_unlocked(pack, unlocked_index, &unlocked_level) {
+                        egui::Color32::GOLD
+                    } else {
+                        egui::Color32::DARK_GRAY
+                    };
+                    ui.painter().line_segment(
+                        [node_center(index), node_center(unlocked_index)],
+                        egui::Stroke::new(3.0, color),
+                    );
+                }
+            }
+
+            for (index, level) in level_index.iter().enumerate() {
+                let bonus_requirement = pack.bonus_level_star_requirement(&level.filename);
+                let is_available = save_data.is_unlocked(pack, index, &level.filename);
+                let level_name = if bonus_requirement.is_some() {
+                    format!("Bonus: {}", format_level_name(&level.filename))
+                } else {
+                    format_level_name(&level.filename)
+                };
+                let caption = if let Some(best) = save_data.best(&level.filename) {
+                    let stars = best.stars.map(|stars| format_stars(stars) + " ");
+                    format!(
+                        "{}\n{}({})",
+                        level_name,
+                        stars.unwrap_or_default(),
+                        format_time(best.time)
                     )
+                } else if let (Some(bonus_requirement), false) = (bonus_requirement, is_available) {
+                    format!("{}\n(requires {} stars)", level_name, bonus_requirement)
+                } else {
+                    level_name
+                };
+                let node_rect = egui::Rect::from_center_size(node_center(index), cell_size * 0.8);
+                let mut response = ui
+                    .put(node_rect, |ui: &mut egui::Ui| {
+                        ui.add_enabled(is_available, egui::Button::new(caption))
+                    })
                     .kbgp_navigation();
-                if index + 1 == level_progress.num_levels_available {
+                if Some(index) == next_level_index {
                     response = response.kbgp_focus_label(FocusLabel::NextLevel);
                 }
                 if Some(&level.filename) == level_progress.current_level.as_ref() {
@@ -156,15 +543,256 @@ fn level_select_menu(
                 if response.clicked() {
                     level_progress.current_level = Some(level.filename.clone());
                     state.set(AppState::LoadLevel).unwrap();
+                    *selected_pack = None;
                 }
             }
         });
     });
 }
 
+fn settings_menu(
+    mut egui_context: ResMut<EguiContext>,
+    mut state: ResMut<State<AppState>>,
+    mut settings: ResMut<GameSettings>,
+    active_profile: Res<ActiveProfile>,
+    mut pkv: ResMut<PkvStore>,
+) {
+    menu_layout(egui_context.ctx_mut(), |ui| {
+        if ui
+            .button("Back")
+            .kbgp_navigation()
+            .kbgp_initial_focus()
+            .clicked()
+            || ui.kbgp_user_action() == Some(MenuActionForKbgp)
+        {
+            settings.save(&mut pkv, &active_profile);
+            state.pop().unwrap();
+            ui.kbgp_clear_input();
+        }
+        // Only touch the resource when something was clicked, because its change detection is
+        // what applies the settings.
+        let current = settings.clone();
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if ui
+                .button(format!("Window Mode: {}", current.window_mode.caption()))
+                .kbgp_navigation()
+                .clicked()
+            {
+                settings.window_mode = next_option(WindowModeSetting::ALL, current.window_mode);
+            }
+            let [width, height] = current.resolution;
+            if ui
+                .button(format!("Resolution: {}x{}", width, height))
+                .kbgp_navigation()
+                .clicked()
+            {
+                settings.resolution = next_option(RESOLUTIONS, current.resolution);
+            }
+        }
+        if ui
+            .button(format!("UI Scale: {:.1}", current.ui_scale))
+            .kbgp_navigation()
+            .clicked()
+        {
+            settings.ui_scale = next_option(UI_SCALES, current.ui_scale);
+        }
+        if ui
+            .button(format!("Stick Deadzone: {:.2}", current.stick_deadzone))
+            .kbgp_navigation()
+            .clicked()
+        {
+            settings.stick_deadzone = next_option(STICK_DEADZONES, current.stick_deadzone);
+        }
+        if ui
+            .button(format!(
+                "Jump With Up: {}",
+                if current.up_to_jump { "On" } else { "Off" }
+            ))
+            .kbgp_navigation()
+            .clicked()
+        {
+            settings.up_to_jump = !current.up_to_jump;
+        }
+        if ui
+            .button(format!(
+                "Master Volume: {:.0}%",
+                100.0 * current.master_volume
+            ))
+            .kbgp_navigation()
+            .clicked()
+        {
+            settings.master_volume = next_option(VOLUMES, current.master_volume);
+        }
+        if ui
+            .button(format!(
+                "Effects Volume: {:.0}%",
+                100.0 * current.sfx_volume
+            ))
+            .kbgp_navigation()
+            .clicked()
+        {
+            settings.sfx_volume = next_option(VOLUMES, current.sfx_volume);
+        }
+        if ui
+            .button(format!(
+                "Music Volume: {:.0}%",
+                100.0 * current.music_volume
+            ))
+            .kbgp_navigation()
+            .clicked()
+        {
+            settings.music_volume = next_option(VOLUMES, current.music_volume);
+        }
+        if ui.button("Controls").kbgp_navigation().clicked() {
+            state.push(AppState::Menu(MenuState::Controls)).unwrap();
+            ui.kbgp_clear_input();
+        }
+    });
+}
+
+struct AwaitingBinding {
+    control: Control,
+    gamepad: bool,
+    /// Only capture after all the inputs are released, so that the press that started the
+    /// rebinding does not get bound.
+    ready: bool,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn controls_menu(
+    mut egui_context: ResMut<EguiContext>,
+    mut state: ResMut<State<AppState>>,
+    mut settings: ResMut<GameSettings>,
+    active_profile: Res<ActiveProfile>,
+    mut pkv: ResMut<PkvStore>,
+    keys: Res<Input<KeyCode>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut awaiting: Local<Option<AwaitingBinding>>,
+    mut editing_player: Local<usize>,
+) {
+    if let Some(awaiting_binding) = awaiting.as_mut() {
+        let control = awaiting_binding.control;
+        if !awaiting_binding.ready {
+            awaiting_binding.ready = keys.get_pressed().next().is_none()
+                && gamepad_buttons.get_pressed().next().is_none();
+        } else if awaiting_binding.gamepad {
+            if let Some(button) = gamepad_buttons
+                .get_just_pressed()
+                .map(|button| button.button_type)
+                .find(|button| !RESERVED_GAMEPAD_BUTTONS.contains(button))
+            {
+                settings
+                    .player_controls_mut(*editing_player)
+                    .set_gamepad_button(control, *editing_player, button);
+                *awaiting = None;
+            }
+        } else if let Some(key) = keys
+            .get_just_pressed()
+            .find(|key| !RESERVED_KEYS.contains(key))
+        {
+            settings
+                .player_controls_mut(*editing_player)
+                .set_key(control, *editing_player, *key);
+            *awaiting = None;
+        }
+    }
+
+    menu_layout(egui_context.ctx_mut(), |ui| {
+        if ui.kbgp_user_action() == Some(MenuActionForKbgp) {
+            if awaiting.is_some() {
+                *awaiting = None;
+            } else {
+                settings.save(&mut pkv, &active_profile);
+                state.pop().unwrap();
+            }
+            ui.kbgp_clear_input();
+            return;
+        }
+        if awaiting.is_some() {
+            // Don't let the pressed inputs navigate the menu
+            ui.kbgp_clear_input();
+        }
+        if ui
+            .button("Back")
+            .kbgp_navigation()
+            .kbgp_initial_focus()
+            .clicked()
+        {
+            settings.save(&mut pkv, &active_profile);
+            state.pop().unwrap();
+            ui.kbgp_clear_input();
+        }
+        if ui
+            .button(format!("Editing: Player {}", *editing_player + 1))
+            .kbgp_navigation()
+            .clicked()
+        {
+            *editing_player = (*editing_player + 1) % MAX_PLAYERS;
+            *awaiting = None;
+        }
+        let controls = settings.player_controls(*editing_player).clone();
+        let conflicting_controls = controls.conflicting_controls(*editing_player);
+        if !conflicting_controls.is_empty() {
+            ui.label(
+                egui::RichText::new("Some inputs are bound to more than one control")
+                    .color(egui::Color32::RED)
+                    .background_color(egui::Color32::BLACK),
+            );
+        }
+        egui::Grid::new("controls").show(ui, |ui| {
+            for control in Control::ALL.iter().copied() {
+                let mut caption =
+                    egui::RichText::new(control.caption()).background_color(egui::Color32::BLACK);
+                if conflicting_controls.contains(&control) {
+                    caption = caption.color(egui::Color32::RED);
+                }
+                ui.label(caption);
+                let binding = controls.binding(control, *editing_player);
+                for gamepad in [false, true] {
+                    let is_awaiting = awaiting
+                        .as_ref()
+                        .map(|awaiting| awaiting.control == control && awaiting.gamepad == gamepad)
+                        .unwrap_or(false);
+                    let text = if is_awaiting {
+                        "Press...".to_owned()
+                    } else if gamepad {
+                        format_inputs(&binding.gamepad_buttons)
+                    } else {
+                        format_inputs(&binding.keys)
+                    };
+                    if ui.button(text).kbgp_navigation().clicked() {
+                        *awaiting = Some(AwaitingBinding {
+                            control,
+                            gamepad,
+                            ready: false,
+                        });
+                    }
+                }
+                ui.end_row();
+            }
+        });
+        if ui.button("Reset To Defaults").kbgp_navigation().clicked() {
+            settings.player_controls_mut(*editing_player).reset();
+            *awaiting = None;
+        }
+    });
+}
+
+fn format_inputs(inputs: &[impl std::fmt::Debug]) -> String {
+    inputs
+        .iter()
+        .map(|input| format!("{:?}", input))
+        .collect::<Vec<_>>()
+        .join(" / ")
+}
+
 fn pause_menu(
     mut egui_context: ResMut<EguiContext>,
     mut state: ResMut<State<AppState>>,
+    checkpoint_state: Res<CheckpointState>,
+    mut respawn_at_checkpoint_writer: EventWriter<RespawnAtCheckpoint>,
+    mut quick_save_writer: EventWriter<QuickSaveRequest>,
     #[cfg(not(target_arch = "wasm32"))] mut exit: EventWriter<bevy::app::AppExit>,
 ) {
     menu_layout(egui_context.ctx_mut(), |ui| {
@@ -177,9 +805,18 @@ fn pause_menu(
         {
             state.set(AppState::Game).unwrap();
         }
+        if checkpoint_state.has_snapshot()
+            && ui.button("Back To Checkpoint").kbgp_navigation().clicked()
+        {
+            respawn_at_checkpoint_writer.send(RespawnAtCheckpoint);
+            state.set(AppState::Game).unwrap();
+        }
         if ui.button("Retry").kbgp_navigation().clicked() {
             state.set(AppState::LoadLevel).unwrap();
         }
+        if ui.button("Quick Save").kbgp_navigation().clicked() {
+            quick_save_writer.send(QuickSaveRequest);
+        }
         if ui
             .button("Level Select")
             .kbgp_navigation()
@@ -190,6 +827,10 @@ fn pause_menu(
             ui.kbgp_clear_input();
             ui.kbgp_set_focus_label(FocusLabel::CurrentLevel);
         }
+        if ui.button("Settings").kbgp_navigation().clicked() {
+            state.push(AppState::Menu(MenuState::Settings)).unwrap();
+            ui.kbgp_clear_input();
+        }
         if ui.button("Main Menu").kbgp_navigation().clicked() {
             state.set(AppState::Menu(MenuState::Main)).unwrap();
             ui.kbgp_clear_input();
@@ -211,6 +852,8 @@ fn level_completed_menu(
     mut egui_context: ResMut<EguiContext>,
     mut state: ResMut<State<AppState>>,
     level_progress: Res<LevelProgress>,
+    current_run_stats: Res<CurrentRunStats>,
+    save_data: Res<SaveData>,
     #[cfg(not(target_arch = "wasm32"))] mut exit: EventWriter<bevy::app::AppExit>,
 ) {
     menu_layout(egui_context.ctx_mut(), |ui| {
@@ -224,6 +867,35 @@ fn level_completed_menu(
                 .background_color(egui::Color32::BLACK)
                 .text_style(egui::TextStyle::Heading),
         );
+        if let Some(completed_run) = &current_run_stats.just_completed {
+            if let Some(stars) = completed_run.stars {
+                ui.label(
+                    egui::RichText::new(format_stars(stars))
+                        .color(egui::Color32::GOLD)
+                        .background_color(egui::Color32::BLACK)
+                        .text_style(egui::TextStyle::Heading),
+                );
+            }
+            ui.label(
+                egui::RichText::new(completed_run.stats.summary())
+                    .color(egui::Color32::WHITE)
+                    .background_color(egui::Color32::BLACK),
+            );
+            if completed_run.is_new_best_time {
+                ui.label(
+                    egui::RichText::new("New Best Time!")
+                        .strong()
+                        .color(egui::Color32::GOLD)
+                        .background_color(egui::Color32::BLACK),
+                );
+            } else if let Some(best) = save_data.best(just_completed) {
+                ui.label(
+                    egui::RichText::new(format!("Best Time: {}", format_time(best.time)))
+                        .color(egui::Color32::WHITE)
+                        .background_color(egui::Color32::BLACK),
+                );
+            }
+        }
         ui.add_space(8.0);
         if let Some(current_level) = &level_progress.current_level {
             if ui
@@ -278,12 +950,24 @@ fn level_completed_menu(
 fn game_over_menu(
     mut egui_context: ResMut<EguiContext>,
     mut state: ResMut<State<AppState>>,
+    checkpoint_state: Res<CheckpointState>,
+    mut respawn_at_checkpoint_writer: EventWriter<RespawnAtCheckpoint>,
     #[cfg(not(target_arch = "wasm32"))] mut exit: EventWriter<bevy::app::AppExit>,
 ) {
     menu_layout(egui_context.ctx_mut(), |ui| {
         if ui.kbgp_user_action() == Some(MenuActionForKbgp) {
             ui.kbgp_set_focus_label(FocusLabel::Exit);
         }
+        if checkpoint_state.has_snapshot()
+            && ui
+                .button("Respawn At Checkpoint")
+                .kbgp_navigation()
+                .kbgp_initial_focus()
+                .clicked()
+        {
+            respawn_at_checkpoint_writer.send(RespawnAtCheckpoint);
+            state.set(AppState::Game).unwrap();
+        }
         if ui
             .button("Retry")
             .kbgp_navigation()
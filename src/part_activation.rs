@@ -3,6 +3,7 @@ use bevy::utils::HashSet;
 
 use crate::global_types::{Activatable, AppState, Carrier, HDirection, IsPowerSource};
 use crate::utils::some_or;
+use crate::world_snapshot::Retired;
 
 pub struct PartActivationPlugin;
 
@@ -15,20 +16,17 @@ impl Plugin for PartActivationPlugin {
     }
 }
 
-fn set_sprite_properties(
-    mut query: Query<(&mut TextureAtlasSprite, &HDirection, Option<&Activatable>)>,
-) {
-    for (mut sprite, hdirection, activatable) in query.iter_mut() {
+fn set_sprite_properties(mut query: Query<(&mut TextureAtlasSprite, &HDirection)>) {
+    for (mut sprite, hdirection) in query.iter_mut() {
         sprite.flip_x = *hdirection == HDirection::Left;
-        if let Some(activatable) = activatable {
-            sprite.index = if activatable.active { 1 } else { 0 };
-        }
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn set_activation_state(
-    mount_base_query: Query<(Entity, &Carrier), With<IsPowerSource>>,
-    carrier_query: Query<&Carrier>,
+    mount_base_query: Query<(Entity, &Carrier), (With<IsPowerSource>, Without<Retired>)>,
+    carrier_query: Query<&Carrier, Without<Retired>>,
+    // Retired parts are included, so that they get deactivated
     mut activatable_query: Query<(Entity, &mut Activatable)>,
 ) {
     let mut parts_to_activate = HashSet::<Entity>::new();
@@ -5,6 +5,7 @@ use bevy_rapier2d::prelude::*;
 use crate::global_types::{Activatable, AppState, Carrier, HDirection};
 use crate::laser::TriggerLaserShot;
 use crate::utils::some_or;
+use crate::world_snapshot::Retired;
 
 pub struct PartBehaviorPlugin;
 
@@ -24,15 +25,19 @@ pub struct HoverBehavior {
     pub range: f32,
 }
 
+#[allow(clippy::type_complexity)]
 fn impl_hover(
-    mut hover_query: Query<(
-        Entity,
-        &Activatable,
-        &HoverBehavior,
-        &Transform,
-        &mut Velocity,
-        &Children,
-    )>,
+    mut hover_query: Query<
+        (
+            Entity,
+            &Activatable,
+            &HoverBehavior,
+            &Transform,
+            &mut Velocity,
+            &Children,
+        ),
+        Without<Retired>,
+    >,
     rapier_context: Res<RapierContext>,
 ) {
     for (entity, activatable, behavior, transform, mut velocity, children) in hover_query.iter_mut()
@@ -67,13 +72,16 @@ pub struct LaserBehavior {
 }
 
 fn impl_laser(
-    mut laser_query: Query<(
-        Entity,
-        &Activatable,
-        &mut LaserBehavior,
-        &Transform,
-        &HDirection,
-    )>,
+    mut laser_query: Query<
+        (
+            Entity,
+            &Activatable,
+            &mut LaserBehavior,
+            &Transform,
+            &HDirection,
+        ),
+        Without<Retired>,
+    >,
     time: Res<Time>,
     mut trigger_laser_shot_writer: EventWriter<TriggerLaserShot>,
 ) {
@@ -101,7 +109,7 @@ pub struct RotatorBehavior {
 }
 
 fn impl_rotator(
-    mut rotator_query: Query<(Entity, &Activatable, &mut RotatorBehavior)>,
+    mut rotator_query: Query<(Entity, &Activatable, &mut RotatorBehavior), Without<Retired>>,
     mut rotating_part_query: Query<(&mut HDirection, Option<&Carrier>)>,
     time: Res<Time>,
 ) {
@@ -0,0 +1,273 @@
+use bevy::prelude::*;
+
+use crate::global_types::{Activatable, AppState};
+use crate::laser::LaserHit;
+use crate::part_behavior::HoverBehavior;
+use crate::world_snapshot::Retired;
+
+pub struct ParticlesPlugin;
+
+impl Plugin for ParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<EmitParticles>();
+        app.add_startup_system(setup_particle_pool);
+        app.add_system_set(SystemSet::on_enter(AppState::LoadLevel).with_system(clear_particles));
+        app.add_system_set({
+            SystemSet::on_update(AppState::Game)
+                .with_system(emit_laser_hit_particles)
+                .with_system(emit_hover_exhaust)
+                .with_system(
+                    emit_particles
+                        .after(emit_laser_hit_particles)
+                        .after(emit_hover_exhaust),
+                )
+                .with_system(update_particles)
+        });
+    }
+}
+
+/// Particles are taken from a fixed pool of entities. When all of them are in use, the oldest
+/// ones are recycled, so effects never spawn new entities.
+const PARTICLE_POOL_SIZE: usize = 512;
+
+/// Hover exhaust particles emitted per second by each active hover.
+const HOVER_EXHAUST_RATE: f32 = 30.0;
+
+#[derive(Clone, Copy, Debug)]
+pub enum ParticleKind {
+    Spark,
+    Debris,
+    Exhaust,
+}
+
+struct ParticleKindSettings {
+    color: Color,
+    size: f32,
+    min_speed: f32,
+    max_speed: f32,
+    /// Maximum angle (in radians) between a particle's direction and the emission direction.
+    spread: f32,
+    lifetime: f32,
+    gravity: f32,
+}
+
+impl ParticleKind {
+    fn settings(&self) -> ParticleKindSettings {
+        match self {
+            ParticleKind::Spark => ParticleKindSettings {
+                color: Color::YELLOW,
+                size: 0.06,
+                min_speed: 2.0,
+                max_speed: 5.0,
+                spread: 1.2,
+                lifetime: 0.3,
+                gravity: 5.0,
+            },
+            ParticleKind::Debris => ParticleKindSettings {
+                color: Color::GRAY,
+                size: 0.12,
+                min_speed: 1.0,
+                max_speed: 4.0,
+                spread: std::f32::consts::PI,
+                lifetime: 0.8,
+                gravity: 15.0,
+            },
+            ParticleKind::Exhaust => ParticleKindSettings {
+                color: Color::rgba(0.6, 0.8, 1.0, 0.6),
+                size: 0.08,
+                min_speed: 1.0,
+                max_speed: 2.0,
+                spread: 0.3,
+                lifetime: 0.4,
+                gravity: 0.0,
+            },
+        }
+    }
+}
+
+pub struct EmitParticles {
+    pub kind: ParticleKind,
+    /// Particles start at random points inside a rectangle of this size around the position.
+    pub position: Vec2,
+    pub area: Vec2,
+    pub direction: Vec2,
+    pub count: usize,
+}
+
+#[derive(Component, Default)]
+struct Particle {
+    velocity: Vec2,
+    gravity: f32,
+    lifetime: f32,
+    time_left: f32,
+    color: Color,
+}
+
+struct ParticlePool {
+    entities: Vec<Entity>,
+    next: usize,
+    /// State of a small xorshift generator - particles don't need a proper random source.
+    rng_state: u32,
+}
+
+impl ParticlePool {
+    fn take(&mut self) -> Entity {
+        let entity = self.entities[self.next];
+        self.next = (self.next + 1) % self.entities.len();
+        entity
+    }
+
+    /// Returns a value between `0.0` and `1.0`.
+    fn random(&mut self) -> f32 {
+        self.rng_state ^= self.rng_state << 13;
+        self.rng_state ^= self.rng_state >> 17;
+        self.rng_state ^= self.rng_state << 5;
+        self.rng_state as f32 / u32::MAX as f32
+    }
+
+    fn random_range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.random()
+    }
+}
+
+fn setup_particle_pool(mut commands: Commands) {
+    let entities = (0..PARTICLE_POOL_SIZE)
+        .map(|_| {
+            let mut cmd = commands.spawn();
+            cmd.insert_bundle(SpriteBundle {
+                visibility: Visibility { is_visible: false },
+                ..Default::default()
+            });
+            cmd.insert(Particle::default());
+            cmd.id()
+        })
+        .collect();
+    commands.insert_resource(ParticlePool {
+        entities,
+        next: 0,
+        rng_state: 0x9e37_79b9,
+    });
+}
+
+fn emit_laser_hit_particles(
+    mut reader: EventReader<LaserHit>,
+    targets_query: Query<(&GlobalTransform, AnyOf<(&Sprite, &TextureAtlasSprite)>)>,
+    mut writer: EventWriter<EmitParticles>,
+) {
+    for laser_hit in reader.iter() {
+        writer.send(EmitParticles {
+            kind: ParticleKind::Spark,
+            position: laser_hit.position,
+            area: Vec2::ZERO,
+            direction: -laser_hit.direction,
+            count: 8,
+        });
+        if !laser_hit.destroyed {
+            continue;
+        }
+        let (center, area) = match targets_query.get(laser_hit.target_entity) {
+            Ok((transform, (sprite, atlas_sprite))) => {
+                let size = sprite
+                    .and_then(|sprite| sprite.custom_size)
+                    .or_else(|| atlas_sprite.and_then(|sprite| sprite.custom_size))
+                    .unwrap_or(Vec2::ONE);
+                (transform.translation().truncate(), size)
+            }
+            Err(_) => (laser_hit.position, Vec2::ONE),
+        };
+        writer.send(EmitParticles {
+            kind: ParticleKind::Debris,
+            position: center,
+            area,
+            direction: Vec2::Y,
+            count: (8.0 * area.x * area.y).clamp(8.0, 64.0) as usize,
+        });
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn emit_hover_exhaust(
+    time: Res<Time>,
+    hovers_query: Query<(&GlobalTransform, &Activatable), (With<HoverBehavior>, Without<Retired>)>,
+    mut pending: Local<f32>,
+    mut writer: EventWriter<EmitParticles>,
+) {
+    *pending += HOVER_EXHAUST_RATE * time.delta_seconds();
+    let count = pending.floor();
+    *pending -= count;
+    if count < 1.0 {
+        return;
+    }
+    for (transform, activatable) in hovers_query.iter() {
+        if !activatable.active {
+            continue;
+        }
+        writer.send(EmitParticles {
+            kind: ParticleKind::Exhaust,
+            position: transform.translation().truncate() - Vec2::new(0.0, 0.2),
+            area: Vec2::new(0.4, 0.0),
+            direction: Vec2::NEG_Y,
+            count: count as usize,
+        });
+    }
+}
+
+fn emit_particles(
+    mut reader: EventReader<EmitParticles>,
+    mut pool: ResMut<ParticlePool>,
+    mut query: Query<(&mut Particle, &mut Transform, &mut Sprite, &mut Visibility)>,
+) {
+    for event in reader.iter() {
+        let settings = event.kind.settings();
+        for _ in 0..event.count {
+            let offset = Vec2::new(
+                pool.random_range(-0.5, 0.5) * event.area.x,
+                pool.random_range(-0.5, 0.5) * event.area.y,
+            );
+            let angle = pool.random_range(-settings.spread, settings.spread);
+            let speed = pool.random_range(settings.min_speed, settings.max_speed);
+            let entity = pool.take();
+            let (mut particle, mut transform, mut sprite, mut visibility) =
+                query.get_mut(entity).unwrap();
+            *particle = Particle {
+                velocity: speed * Vec2::from_angle(angle).rotate(event.direction),
+                gravity: settings.gravity,
+                lifetime: settings.lifetime,
+                time_left: settings.lifetime,
+                color: settings.color,
+            };
+            *transform = Transform::from_translation((event.position + offset).extend(5.0));
+            sprite.color = settings.color;
+            sprite.custom_size = Some(Vec2::splat(settings.size));
+            visibility.is_visible = true;
+        }
+    }
+}
+
+fn update_particles(
+    time: Res<Time>,
+    mut query: Query<(&mut Particle, &mut Transform, &mut Sprite, &mut Visibility)>,
+) {
+    let delta = time.delta_seconds();
+    for (mut particle, mut transform, mut sprite, mut visibility) in query.iter_mut() {
+        if particle.time_left <= 0.0 {
+            continue;
+        }
+        particle.time_left -= delta;
+        if particle.time_left <= 0.0 {
+            visibility.is_visible = false;
+            continue;
+        }
+        particle.velocity.y -= particle.gravity * delta;
+        transform.translation += (particle.velocity * delta).extend(0.0);
+        let alpha = particle.color.a() * particle.time_left / particle.lifetime;
+        sprite.color.set_a(alpha);
+    }
+}
+
+fn clear_particles(mut query: Query<(&mut Particle, &mut Visibility)>) {
+    for (mut particle, mut visibility) in query.iter_mut() {
+        particle.time_left = 0.0;
+        visibility.is_visible = false;
+    }
+}
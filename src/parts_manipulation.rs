@@ -1,39 +1,82 @@
 use bevy::ecs::query::QueryEntityError;
 use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
+use bevy::utils::HashSet;
 use bevy_rapier2d::prelude::*;
 use float_ord::FloatOrd;
 use leafwing_input_manager::prelude::ActionState;
 
-use crate::global_types::{AppState, Carrier, HalfHeight, InputBinding, IsMountBase, Pickable};
+use crate::global_types::{
+    AppState, Carrier, HDirection, HalfHeight, InputBinding, IsMountBase, Pickable,
+};
 use crate::physics_utils::standing_on;
+use crate::player_control::{PlayerAction, Stunned};
 use crate::utils::some_or;
+use crate::world_snapshot::Retired;
 
 pub struct PartsManipulationPlugin;
 
 impl Plugin for PartsManipulationPlugin {
     fn build(&self, app: &mut App) {
+        app.add_event::<PartMounted>();
         app.add_system_set({
             SystemSet::on_update(AppState::Game)
                 .with_system(apply_carrying)
+                .with_system(clear_dangling_carrying.before(control_pickup))
                 .with_system(control_pickup)
                 .with_system(detect_mounting)
         });
     }
 }
 
+/// Sent when a part lands on a mount base and gets attached to it.
+pub struct PartMounted {
+    pub part_entity: Entity,
+    pub mount_base_entity: Entity,
+}
+
 #[derive(Component)]
 struct ChangeCarrying {
     carrier_entity: Entity,
     old_carrier_entity: Option<Entity>,
+    /// Instead of swapping places with the carrier (which is standing on it), move the part - and
+    /// whatever is stacked on it - to the top of the carrier.
+    lift: bool,
 }
 
+/// How far (horizontally, between centers) the player can reach for a part beside them.
+const GRAB_REACH: f32 = 1.25;
+
 #[derive(SystemParam)]
 struct SwapPlaces<'w, 's> {
     query: Query<'w, 's, (&'static mut Transform, &'static HalfHeight)>,
 }
 
 impl SwapPlaces<'_, '_> {
+    /// Moves `top`, and everything in `stack` along with it, so that `top` rests on `bottom`.
+    fn lift_onto(
+        &mut self,
+        top: Entity,
+        bottom: Entity,
+        stack: &[Entity],
+    ) -> Result<f32, QueryEntityError> {
+        let [(top_transform, HalfHeight(top_hh)), (bot_transform, HalfHeight(bot_hh))] =
+            self.query.get_many([top, bottom])?;
+        let combined_hh = top_hh + bot_hh;
+        let target = bot_transform.translation + Vec3::new(0.0, 0.01 + combined_hh, 0.0);
+        let offset = Vec3::new(
+            target.x - top_transform.translation.x,
+            target.y - top_transform.translation.y,
+            0.0,
+        );
+        for entity in std::iter::once(top).chain(stack.iter().copied()) {
+            if let Ok((mut transform, _)) = self.query.get_mut(entity) {
+                transform.translation += offset;
+            }
+        }
+        Ok(combined_hh)
+    }
+
     fn swap_places(&mut self, top: Entity, bottom: Entity) -> Result<f32, QueryEntityError> {
         self.query.get_many_mut([top, bottom]).map(move |query_result| {
             let [
@@ -47,14 +90,44 @@ impl SwapPlaces<'_, '_> {
     }
 }
 
+/// The horizontal direction (`-1.0` or `1.0`) a carried part would be thrown to. Without a
+/// held direction, parts are thrown to where the player is facing.
+pub fn throw_direction(action_state: &ActionState<InputBinding>, facing: &HDirection) -> f32 {
+    action_state
+        .axis_pair(InputBinding::Move)
+        .map(|input| input.x())
+        .filter(|x| 0.5 <= x.abs())
+        .map(f32::signum)
+        .unwrap_or_else(|| facing.as_x())
+}
+
+/// Where a thrown part is placed, relative to where it was carried.
+pub fn throw_offset(throw_direction: f32) -> Vec3 {
+    Vec3::new(0.75 * throw_direction, -0.2, 0.0)
+}
+
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
 fn control_pickup(
-    mut player_query: Query<(&ActionState<InputBinding>, Entity), With<Carrier>>,
+    mut player_query: Query<
+        (
+            &ActionState<InputBinding>,
+            Entity,
+            &GlobalTransform,
+            &HDirection,
+        ),
+        (With<Carrier>, Without<Stunned>),
+    >,
     mut pickable_query: Query<(&mut Pickable, &mut Transform, &mut Velocity)>,
+    grab_candidates_query: Query<(Entity, &GlobalTransform), (With<Pickable>, Without<Retired>)>,
+    mount_bases_query: Query<(), With<IsMountBase>>,
     mut carrier_query: Query<&mut Carrier>,
+    half_height_query: Query<&HalfHeight>,
+    children_query: Query<&Children>,
     rapier_context: Res<RapierContext>,
     mut commands: Commands,
+    mut player_action_writer: EventWriter<PlayerAction>,
 ) {
-    for (action_state, player_entity) in player_query.iter_mut() {
+    for (action_state, player_entity, player_transform, facing) in player_query.iter_mut() {
         if !action_state.just_pressed(InputBinding::Pickup) {
             continue;
         }
@@ -73,28 +146,20 @@ fn control_pickup(
         });
         let mut carrier = carrier_query.get_mut(player_entity).unwrap();
         if let Some(pickable_entity) = carrier.carrying {
-            let throw_direction = action_state
-                .axis_pair(InputBinding::Move)
-                .and_then(|input| {
-                    let x = input.x();
-                    if x.abs() < 0.5 {
-                        None
-                    } else {
-                        Some(x.signum())
-                    }
-                });
-            let throw_direction = some_or!(throw_direction; continue);
+            let throw_direction = throw_direction(action_state, facing);
             let (mut pickable, mut pickable_transform, mut pickable_velocity) = pickable_query
                 .get_mut(pickable_entity)
                 .expect("Player should only be able to carry pickable entities");
-            pickable_transform.translation += Vec3::new(0.75 * throw_direction, -0.2, 0.0);
+            pickable_transform.translation += throw_offset(throw_direction);
             pickable_velocity.linvel += Vec2::new(0.0, -3.0);
             commands.entity(pickable_entity).remove::<ImpulseJoint>();
             carrier.carrying = None;
             pickable.carried_by = None;
-        } else if let Some((_offset_this, _offset_that, standing_on_entity)) = standing_on {
-            let (pickable, _, _) =
-                some_or!(pickable_query.get_mut(standing_on_entity).ok(); continue);
+            player_action_writer.send(PlayerAction::Throw);
+        } else if let Some((_offset_this, _offset_that, standing_on_entity)) = standing_on
+            .filter(|(_, _, standing_on_entity)| pickable_query.contains(*standing_on_entity))
+        {
+            let (pickable, _, _) = pickable_query.get(standing_on_entity).unwrap();
             let pickable_entity = standing_on_entity;
 
             commands.entity(pickable_entity).remove::<ImpulseJoint>();
@@ -102,7 +167,87 @@ fn control_pickup(
             commands.entity(pickable_entity).insert(ChangeCarrying {
                 carrier_entity: player_entity,
                 old_carrier_entity: pickable.carried_by,
+                lift: false,
+            });
+            player_action_writer.send(PlayerAction::Pickup);
+        } else {
+            let direction = throw_direction(action_state, facing);
+            let player_position = player_transform.translation();
+            let nearest = grab_candidates_query
+                .iter()
+                .filter_map(|(entity, transform)| {
+                    let offset = transform.translation() - player_position;
+                    let distance = offset.x * direction;
+                    (0.0 < distance && distance <= GRAB_REACH && offset.y.abs() < 0.5)
+                        .then_some((entity, distance))
+                })
+                .min_by_key(|(_, distance)| FloatOrd(*distance));
+            let (pickable_entity, _) = some_or!(nearest; continue);
+            let (pickable, pickable_transform, _) = pickable_query.get(pickable_entity).unwrap();
+            // Don't take parts from other players
+            if let Some(carried_by) = pickable.carried_by {
+                if !mount_bases_query.contains(carried_by) {
+                    continue;
+                }
+            }
+            // The part is lifted along with everything stacked on it, so make sure the whole stack
+            // has room to move over the player's head.
+            let mut stack = vec![pickable_entity];
+            while let Some(next) = carrier_query
+                .get(*stack.last().unwrap())
+                .ok()
+                .and_then(|carrier| carrier.carrying)
+            {
+                stack.push(next);
+            }
+            let stack_height: f32 = stack
+                .iter()
+                .filter_map(|entity| half_height_query.get(*entity).ok())
+                .map(|HalfHeight(hh)| 2.0 * hh)
+                .sum();
+            let HalfHeight(pickable_hh) =
+                some_or!(half_height_query.get(pickable_entity).ok(); continue);
+            let HalfHeight(player_hh) =
+                some_or!(half_height_query.get(player_entity).ok(); continue);
+            let stack_center = pickable_transform.translation.truncate()
+                + Vec2::new(0.0, 0.5 * stack_height - pickable_hh);
+            let lifted_center =
+                player_position.truncate() + Vec2::new(0.0, player_hh + 0.01 + 0.5 * stack_height);
+            let ignore: HashSet<Entity> = std::iter::once(player_entity)
+                .chain(stack.iter().copied())
+                .flat_map(|entity| {
+                    std::iter::once(entity).chain(
+                        children_query
+                            .get(entity)
+                            .into_iter()
+                            .flat_map(|children| children.iter().copied()),
+                    )
+                })
+                .collect();
+            if rapier_context
+                .cast_shape(
+                    stack_center,
+                    0.0,
+                    lifted_center - stack_center,
+                    // Slightly smaller than the stack, so that merely touching the floor or the
+                    // neighboring parts does not count as an obstruction
+                    &Collider::cuboid(0.45, 0.5 * stack_height - 0.05),
+                    1.0,
+                    QueryFilter::default()
+                        .exclude_sensors()
+                        .predicate(&|other_entity| !ignore.contains(&other_entity)),
+                )
+                .is_some()
+            {
+                continue;
+            }
+            commands.entity(pickable_entity).remove::<ImpulseJoint>();
+            commands.entity(pickable_entity).insert(ChangeCarrying {
+                carrier_entity: player_entity,
+                old_carrier_entity: pickable.carried_by,
+                lift: true,
             });
+            player_action_writer.send(PlayerAction::Pickup);
         }
     }
 }
@@ -114,6 +259,7 @@ fn detect_mounting(
     mut transform_query: Query<&mut Transform>,
     global_transform_query: Query<&GlobalTransform>,
     mut commands: Commands,
+    mut part_mounted_writer: EventWriter<PartMounted>,
 ) {
     for event in reader.iter() {
         if let &CollisionEvent::Started(e1, e2, _) = event {
@@ -154,6 +300,10 @@ fn detect_mounting(
             commands
                 .entity(pickable_entity)
                 .insert(ImpulseJoint::new(carrier_entity, joint));
+            part_mounted_writer.send(PartMounted {
+                part_entity: pickable_entity,
+                mount_base_entity: carrier_entity,
+            });
         }
     }
 }
@@ -169,12 +319,28 @@ fn apply_carrying(
         &ChangeCarrying {
             carrier_entity,
             old_carrier_entity,
+            lift,
         },
         mut pickable,
     ) in pickable_query.iter_mut()
     {
+        let placement = if lift {
+            let mut stack = Vec::new();
+            let mut stack_top = pickable_entity;
+            while let Some(next) = carrier_query
+                .get(stack_top)
+                .ok()
+                .and_then(|carrier| carrier.carrying)
+            {
+                stack.push(next);
+                stack_top = next;
+            }
+            swap_places.lift_onto(pickable_entity, carrier_entity, &stack)
+        } else {
+            swap_places.swap_places(carrier_entity, pickable_entity)
+        };
         let mut carrier = some_or!(carrier_query.get_mut(carrier_entity).ok(); continue);
-        if let Ok(combined_hh) = swap_places.swap_places(carrier_entity, pickable_entity) {
+        if let Ok(combined_hh) = placement {
             let joint = FixedJointBuilder::new().local_anchor1(Vec2::new(0.0, 0.01 + combined_hh));
             commands
                 .entity(pickable_entity)
@@ -190,3 +356,27 @@ fn apply_carrying(
         }
     }
 }
+
+/// Parts can be destroyed while carrying or being carried, which leaves the other side of the
+/// relation pointing to an entity that is no longer in play.
+fn clear_dangling_carrying(
+    mut carrier_query: Query<&mut Carrier, Without<Retired>>,
+    mut pickable_query: Query<(Entity, &mut Pickable), Without<Retired>>,
+    mut commands: Commands,
+) {
+    for mut carrier in carrier_query.iter_mut() {
+        if let Some(carried_entity) = carrier.carrying {
+            if !pickable_query.contains(carried_entity) {
+                carrier.carrying = None;
+            }
+        }
+    }
+    for (pickable_entity, mut pickable) in pickable_query.iter_mut() {
+        if let Some(carrier_entity) = pickable.carried_by {
+            if !carrier_query.contains(carrier_entity) {
+                pickable.carried_by = None;
+                commands.entity(pickable_entity).remove::<ImpulseJoint>();
+            }
+        }
+    }
+}
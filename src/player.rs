@@ -1,13 +1,14 @@
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 use bevy_yoleck::{YoleckEdit, YoleckExtForApp, YoleckPopulate, YoleckTypeHandler};
-use leafwing_input_manager::prelude::{DualAxis, InputMap, VirtualDPad};
 use leafwing_input_manager::InputManagerBundle;
 use serde::{Deserialize, Serialize};
 
-use crate::global_types::{Carrier, HalfHeight, InputBinding, IsPlayer};
+use crate::animation::{AnimationState, SpriteAnimation};
+use crate::global_types::{Carrier, HDirection, HalfHeight, IsPlayer, PlayerIndex};
 use crate::loading::GameAssets;
 use crate::player_control::PlayerControl;
+use crate::settings::{GameSettings, MAX_PLAYERS};
 
 pub struct PlayerPlugin;
 
@@ -30,10 +31,20 @@ impl Plugin for PlayerPlugin {
 pub struct Player {
     #[serde(default)]
     position: Vec2,
+    /// Zero-based. Players beyond the number of players in the session are not spawned.
+    #[serde(default)]
+    player_index: usize,
 }
 
-fn populate(mut populate: YoleckPopulate<Player>, game_assets: Res<GameAssets>) {
-    populate.populate(|_ctx, _data, mut cmd| {
+fn populate(
+    mut populate: YoleckPopulate<Player>,
+    game_assets: Res<GameAssets>,
+    settings: Res<GameSettings>,
+) {
+    populate.populate(|ctx, data, mut cmd| {
+        if !ctx.is_in_editor() && !settings.is_player_active(data.player_index) {
+            return;
+        }
         cmd.insert_bundle(SpriteSheetBundle {
             sprite: TextureAtlasSprite {
                 custom_size: Some(Vec2::new(1.0, 1.0)),
@@ -43,9 +54,12 @@ fn populate(mut populate: YoleckPopulate<Player>, game_assets: Res<GameAssets>)
             ..Default::default()
         });
         cmd.insert(IsPlayer);
+        cmd.insert(PlayerIndex(data.player_index));
         cmd.insert(PlayerControl::default());
         cmd.insert(Carrier::default());
         cmd.insert(HalfHeight(0.5));
+        cmd.insert(HDirection::Right);
+        cmd.insert(SpriteAnimation::new(AnimationState::Idle));
 
         cmd.insert(RigidBody::Dynamic);
         cmd.insert(Collider::cuboid(0.25, 0.5));
@@ -56,24 +70,27 @@ fn populate(mut populate: YoleckPopulate<Player>, game_assets: Res<GameAssets>)
 }
 
 fn edit(mut edit: YoleckEdit<Player>, mut _commands: Commands) {
-    edit.edit(|_ctx, _data, _ui| {});
+    edit.edit(|_ctx, data, ui| {
+        ui.horizontal(|ui| {
+            for player_index in 0..MAX_PLAYERS {
+                ui.selectable_value(
+                    &mut data.player_index,
+                    player_index,
+                    format!("Player {}", player_index + 1),
+                );
+            }
+        });
+    });
 }
 
-fn add_player_input(mut populate: YoleckPopulate<Player>) {
-    populate.populate(|ctx, _data, mut cmd| {
-        if ctx.is_in_editor() {
+fn add_player_input(mut populate: YoleckPopulate<Player>, settings: Res<GameSettings>) {
+    populate.populate(|ctx, data, mut cmd| {
+        if ctx.is_in_editor() || !settings.is_player_active(data.player_index) {
             return;
         }
         cmd.insert_bundle(InputManagerBundle {
             action_state: Default::default(),
-            input_map: InputMap::default()
-                .insert(VirtualDPad::arrow_keys(), InputBinding::Move)
-                .insert(VirtualDPad::wasd(), InputBinding::Move)
-                .insert(VirtualDPad::dpad(), InputBinding::Move)
-                .insert(DualAxis::left_stick(), InputBinding::Move)
-                .insert(KeyCode::Space, InputBinding::Pickup)
-                .insert(GamepadButtonType::South, InputBinding::Pickup)
-                .build(),
+            input_map: settings.build_input_map(data.player_index),
         });
     });
 }
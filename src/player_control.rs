@@ -1,5 +1,6 @@
-use crate::global_types::{AppState, Carrier, InputBinding, IsPlayer};
+use crate::global_types::{AppState, HDirection, InputBinding, IsPlayer, PlayerIndex};
 use crate::physics_utils::standing_on;
+use crate::settings::GameSettings;
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 use leafwing_input_manager::prelude::{ActionState, InputManagerPlugin};
@@ -9,8 +10,13 @@ pub struct PlayerControlPlugin;
 impl Plugin for PlayerControlPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(InputManagerPlugin::<InputBinding>::default());
-        app.add_system_set(SystemSet::on_update(AppState::Game).with_system(control_player));
-        app.add_system(update_player_sprite_index);
+        app.add_event::<PlayerAction>();
+        app.add_system_set({
+            SystemSet::on_update(AppState::Game)
+                .with_system(control_player)
+                .with_system(recover_from_stun)
+        });
+        app.add_system(update_player_sprite_color);
         app.insert_resource(PlayerMovementSettings {
             max_speed: 10.0,
             impulse_exponent: 4.0,
@@ -27,6 +33,8 @@ impl Plugin for PlayerControlPlugin {
             stood_on_time_coefficient: 10.0,
             uphill_move_exponent: 0.5,
             downhill_brake_exponent: 1.0,
+            jump_buffer_time: 0.1,
+            coyote_time: 0.1,
         });
     }
 }
@@ -36,6 +44,9 @@ pub struct PlayerControl {
     mid_jump: bool,
     last_stood_on: Vec2,
     stood_on_potential: f32,
+    jump_held: bool,
+    jump_buffer_left: f32,
+    coyote_time_left: f32,
 }
 
 impl Default for PlayerControl {
@@ -44,10 +55,24 @@ impl Default for PlayerControl {
             mid_jump: false,
             last_stood_on: Vec2::Y,
             stood_on_potential: 0.0,
+            jump_held: false,
+            jump_buffer_left: 0.0,
+            coyote_time_left: 0.0,
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerAction {
+    Jump,
+    Pickup,
+    Throw,
+}
+
+/// A stunned player cannot move or pick up parts until the timer finishes.
+#[derive(Component)]
+pub struct Stunned(pub Timer);
+
 struct PlayerMovementSettings {
     pub max_speed: f32,
     pub impulse_exponent: f32,
@@ -64,28 +89,61 @@ struct PlayerMovementSettings {
     pub stood_on_time_coefficient: f32,
     pub uphill_move_exponent: f32,
     pub downhill_brake_exponent: f32,
+    /// A jump pressed this long before landing will still happen when landing.
+    pub jump_buffer_time: f32,
+    /// The player can still jump this long after walking off a ledge.
+    pub coyote_time: f32,
 }
 
 fn control_player(
     time: Res<Time>,
-    mut query: Query<(
-        &ActionState<InputBinding>,
-        Entity,
-        &mut Velocity,
-        &mut PlayerControl,
-    )>,
+    mut query: Query<
+        (
+            &ActionState<InputBinding>,
+            Entity,
+            &mut Velocity,
+            &mut PlayerControl,
+            &mut HDirection,
+        ),
+        Without<Stunned>,
+    >,
     player_movement_settings: Res<PlayerMovementSettings>,
+    game_settings: Res<GameSettings>,
     rapier_context: Res<RapierContext>,
+    mut player_action_writer: EventWriter<PlayerAction>,
 ) {
-    for (action_state, player_entity, mut velocity, mut player_control) in query.iter_mut() {
+    for (action_state, player_entity, mut velocity, mut player_control, mut facing) in
+        query.iter_mut()
+    {
         let movement_value;
         let is_jumping;
         if let Some(movement_input) = action_state.clamped_axis_pair(InputBinding::Move) {
             movement_value = movement_input.x();
-            is_jumping = 0.5 < movement_input.y();
+            is_jumping = action_state.pressed(InputBinding::Jump)
+                || (game_settings.up_to_jump && 0.5 < movement_input.y());
         } else {
             continue;
         }
+        if 0.5 <= movement_value.abs() {
+            *facing = if movement_value < 0.0 {
+                HDirection::Left
+            } else {
+                HDirection::Right
+            };
+        }
+
+        if is_jumping && !player_control.jump_held {
+            player_control.jump_buffer_left = player_movement_settings.jump_buffer_time;
+        } else {
+            player_control.jump_buffer_left =
+                (player_control.jump_buffer_left - time.delta_seconds()).max(0.0);
+        }
+        player_control.jump_held = is_jumping;
+        // Holding the jump button only continues a jump - it does not start a new one. A recent
+        // press starts one even if it was already released, so that a quick tap just before
+        // landing is not lost.
+        let wants_to_jump =
+            (is_jumping && player_control.mid_jump) || 0.0 < player_control.jump_buffer_left;
 
         let target_speed = movement_value;
         let standing_on = standing_on(&rapier_context, player_entity, |ed| ed.normal);
@@ -103,7 +161,8 @@ fn control_player(
                 player_control.last_stood_on = standing_on;
                 player_control.stood_on_potential = 1.0;
                 if 0.0 < standing_on.dot(Vec2::Y) {
-                    if is_jumping {
+                    player_control.coyote_time_left = player_movement_settings.coyote_time;
+                    if wants_to_jump {
                         return JumpStatus::InitiateJump;
                     }
                     return JumpStatus::CanJump;
@@ -112,6 +171,11 @@ fn control_player(
             player_control.stood_on_potential = (player_control.stood_on_potential
                 - time.delta().as_secs_f32() * player_movement_settings.stood_on_time_coefficient)
                 .max(0.0);
+            player_control.coyote_time_left =
+                (player_control.coyote_time_left - time.delta_seconds()).max(0.0);
+            if wants_to_jump && !player_control.mid_jump && 0.0 < player_control.coyote_time_left {
+                return JumpStatus::InitiateJump;
+            }
 
             if 0.0 <= velocity.linvel.y {
                 if is_jumping && player_control.mid_jump {
@@ -129,7 +193,15 @@ fn control_player(
                 player_control.mid_jump = false;
             }
             JumpStatus::InitiateJump => {
+                if !player_control.mid_jump {
+                    player_action_writer.send(PlayerAction::Jump);
+                    // The buffered press is used up
+                    player_control.jump_buffer_left = 0.0;
+                }
                 player_control.mid_jump = true;
+                // Whether from the ground or during coyote time, don't allow another jump in
+                // mid-air
+                player_control.coyote_time_left = 0.0;
                 let max_vertical_impulse_allowed =
                     player_movement_settings.uphil_vertical_cap - velocity.linvel.y;
                 if 0.0 < max_vertical_impulse_allowed {
@@ -214,10 +286,30 @@ fn control_player(
     }
 }
 
-fn update_player_sprite_index(
-    mut query: Query<(&mut TextureAtlasSprite, &Carrier), With<IsPlayer>>,
+fn recover_from_stun(
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Stunned)>,
+    mut commands: Commands,
+) {
+    for (entity, mut stunned) in query.iter_mut() {
+        stunned.0.tick(time.delta());
+        if stunned.0.finished() {
+            commands.entity(entity).remove::<Stunned>();
+        }
+    }
+}
+
+fn update_player_sprite_color(
+    mut query: Query<(&mut TextureAtlasSprite, Option<&Stunned>, &PlayerIndex), With<IsPlayer>>,
 ) {
-    for (mut sprite, carrier) in query.iter_mut() {
-        sprite.index = if carrier.carrying.is_some() { 1 } else { 0 };
+    for (mut sprite, stunned, player_index) in query.iter_mut() {
+        sprite.color = if stunned.is_some() {
+            Color::rgb(1.0, 0.5, 0.5)
+        } else if player_index.0 == 0 {
+            Color::WHITE
+        } else {
+            // Tell the second player apart in co-op
+            Color::rgb(0.6, 0.8, 1.0)
+        };
     }
 }
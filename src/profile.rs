@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+use bevy_pkv::PkvStore;
+use serde::{Deserialize, Serialize};
+
+pub struct ProfilePlugin;
+
+impl Plugin for ProfilePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Profiles>();
+        app.init_resource::<ActiveProfile>();
+        app.add_event::<ProfileCopied>();
+        app.add_event::<ProfileDeleted>();
+        app.add_startup_system(read_profiles);
+    }
+}
+
+const PROFILES_PKV_KEY: &str = "profiles";
+
+/// Data saved before profiles were introduced is migrated into this profile.
+pub const FIRST_PROFILE_ID: u32 = 0;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ProfileInfo {
+    pub id: u32,
+    pub name: String,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Profiles {
+    pub profiles: Vec<ProfileInfo>,
+    /// Ids are never reused, so that a new profile will never pick up data of a deleted one.
+    next_id: u32,
+    pub last_active: Option<u32>,
+}
+
+impl Profiles {
+    pub fn get(&self, id: u32) -> Option<&ProfileInfo> {
+        self.profiles.iter().find(|profile| profile.id == id)
+    }
+
+    pub fn create(&mut self, name: String) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.profiles.push(ProfileInfo { id, name });
+        id
+    }
+
+    pub fn rename(&mut self, id: u32, name: String) {
+        if let Some(profile) = self.profiles.iter_mut().find(|profile| profile.id == id) {
+            profile.name = name;
+        }
+    }
+
+    /// Only creates the profile entry - the data itself is copied by the systems that own it when
+    /// they receive [`ProfileCopied`].
+    pub fn copy(&mut self, id: u32) -> Option<u32> {
+        let name = format!("{} (copy)", self.get(id)?.name);
+        Some(self.create(name))
+    }
+
+    /// Only removes the profile entry - the data itself is cleared by the systems that own it
+    /// when they receive [`ProfileDeleted`].
+    pub fn delete(&mut self, id: u32) {
+        self.profiles.retain(|profile| profile.id != id);
+        if self.last_active == Some(id) {
+            self.last_active = None;
+        }
+    }
+
+    pub fn save(&self, pkv: &mut PkvStore) {
+        if let Err(err) = pkv.set(PROFILES_PKV_KEY, self) {
+            error!("Cannot save profiles: {}", err);
+        }
+    }
+}
+
+/// Systems that keep per-profile data should reload it when this resource changes.
+#[derive(Default)]
+pub struct ActiveProfile(pub Option<u32>);
+
+impl ActiveProfile {
+    pub fn pkv_key(&self, key: &str) -> Option<String> {
+        Some(profile_pkv_key(self.0?, key))
+    }
+}
+
+pub fn profile_pkv_key(profile_id: u32, key: &str) -> String {
+    format!("profile_{}_{}", profile_id, key)
+}
+
+pub struct ProfileCopied {
+    pub from: u32,
+    pub to: u32,
+}
+
+pub struct ProfileDeleted {
+    pub id: u32,
+}
+
+fn read_profiles(
+    mut pkv: ResMut<PkvStore>,
+    mut profiles: ResMut<Profiles>,
+    mut active_profile: ResMut<ActiveProfile>,
+) {
+    if let Ok(loaded) = pkv.get::<Profiles>(PROFILES_PKV_KEY) {
+        *profiles = loaded;
+    } else {
+        let id = profiles.create("Player 1".to_owned());
+        assert_eq!(id, FIRST_PROFILE_ID);
+        profiles.last_active = Some(id);
+        profiles.save(&mut pkv);
+    }
+    active_profile.0 = profiles.last_active;
+}
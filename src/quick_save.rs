@@ -0,0 +1,325 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_pkv::PkvStore;
+use bevy_rapier2d::prelude::Velocity;
+use bevy_yoleck::YoleckManaged;
+use serde::{Deserialize, Serialize};
+
+use crate::global_types::{AppState, HDirection, LevelProgress};
+use crate::level_stats::{CurrentRunStats, RunStats};
+use crate::profile::{
+    profile_pkv_key, ActiveProfile, ProfileCopied, ProfileDeleted, FIRST_PROFILE_ID,
+};
+use crate::utils::some_or;
+use crate::world_snapshot::{EntitySnapshot, WorldSnapshot, WorldSnapshotter};
+use crate::yoleck_utils::LevelEntityOrigin;
+
+pub struct QuickSavePlugin;
+
+impl Plugin for QuickSavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<QuickSaveSlot>();
+        app.add_event::<QuickSaveRequest>();
+        app.add_event::<QuickLoadRequest>();
+        app.add_system(read_quick_save);
+        app.add_system(copy_profile_quick_save);
+        app.add_system(clear_deleted_profile_quick_save);
+        app.add_system(handle_quick_save_request);
+        app.add_system(handle_quick_load_request);
+        app.add_system_set(
+            SystemSet::on_update(AppState::Game).with_system(apply_pending_quick_load),
+        );
+        app.add_system_set(
+            SystemSet::on_enter(AppState::LevelCompleted).with_system(clear_completed_quick_save),
+        );
+    }
+}
+
+/// Holds an `Option<QuickSave>`. The store cannot remove keys, so an emptied slot is stored as
+/// `None`.
+const QUICK_SAVE_PKV_KEY: &str = "quick_save";
+/// Before profiles, the quick-save was stored without a profile prefix, and without the option of
+/// storing an empty slot.
+const LEGACY_QUICK_SAVE_PKV_KEY: &str = "quick_save";
+
+pub struct QuickSaveRequest;
+
+pub struct QuickLoadRequest;
+
+#[derive(Default)]
+pub struct QuickSaveSlot {
+    saved: Option<QuickSave>,
+    pending_load: bool,
+}
+
+impl QuickSaveSlot {
+    pub fn saved_level(&self) -> Option<&str> {
+        Some(self.saved.as_ref()?.level.as_str())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct QuickSave {
+    level: String,
+    entities: Vec<SavedEntity>,
+    #[serde(default)]
+    stats: RunStats,
+}
+
+/// Uses the type name from the level files rather than the Rust type, so that saves survive code
+/// refactors.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+struct SavedEntityKey {
+    type_name: String,
+    position: IVec2,
+}
+
+impl SavedEntityKey {
+    fn new(managed: &YoleckManaged, origin: &LevelEntityOrigin) -> Self {
+        Self {
+            type_name: managed.type_name.clone(),
+            position: origin.position,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct SavedEntity {
+    key: SavedEntityKey,
+    translation: Vec3,
+    velocity: Option<(Vec2, f32)>,
+    hdirection: Option<HDirection>,
+    carried_by: Option<SavedEntityKey>,
+    carrying: Option<SavedEntityKey>,
+    breakable_life: Option<f32>,
+    retired: bool,
+}
+
+/// Entities that share a key cannot be told apart when loading, so they are left out of the
+/// quick-save and start the way the level file places them.
+fn unique_entity_keys<'a>(
+    origins: impl Iterator<Item = (Entity, &'a YoleckManaged, &'a LevelEntityOrigin)>,
+) -> HashMap<Entity, SavedEntityKey> {
+    let mut entity_by_key = HashMap::<SavedEntityKey, Option<Entity>>::default();
+    for (entity, managed, origin) in origins {
+        let key = SavedEntityKey::new(managed, origin);
+        if let Some(existing) = entity_by_key.get_mut(&key) {
+            if existing.take().is_some() {
+                warn!(
+                    "Multiple {} entities at {} - their state will not be quick-saved",
+                    key.type_name, key.position
+                );
+            }
+        } else {
+            entity_by_key.insert(key, Some(entity));
+        }
+    }
+    entity_by_key
+        .into_iter()
+        .filter_map(|(key, entity)| Some((entity?, key)))
+        .collect()
+}
+
+fn read_quick_save(
+    mut pkv: ResMut<PkvStore>,
+    active_profile: Res<ActiveProfile>,
+    mut quick_save_slot: ResMut<QuickSaveSlot>,
+) {
+    if !active_profile.is_changed() {
+        return;
+    }
+    let profile_id = some_or!(active_profile.0; {
+        quick_save_slot.saved = None;
+        return;
+    });
+    let key = profile_pkv_key(profile_id, QUICK_SAVE_PKV_KEY);
+    quick_save_slot.saved = if let Ok(saved) = pkv.get::<Option<QuickSave>>(&key) {
+        saved
+    } else if profile_id == FIRST_PROFILE_ID {
+        let migrated = migrate_legacy_quick_save(&pkv);
+        if let Err(err) = pkv.set(&key, &migrated) {
+            error!("Cannot migrate quick-save: {}", err);
+        }
+        migrated
+    } else {
+        None
+    };
+}
+
+fn migrate_legacy_quick_save(pkv: &PkvStore) -> Option<QuickSave> {
+    let mut quick_save = pkv.get::<QuickSave>(LEGACY_QUICK_SAVE_PKV_KEY).ok()?;
+    // Legacy quick-saves keyed the entities by their Rust type path, which ends with the Yoleck type
+    // name.
+    let strip_type_path = |key: &mut SavedEntityKey| {
+        if let Some((_, type_name)) = key.type_name.rsplit_once("::") {
+            key.type_name = type_name.to_owned();
+        }
+    };
+    for saved in quick_save.entities.iter_mut() {
+        strip_type_path(&mut saved.key);
+        saved.carried_by.iter_mut().for_each(strip_type_path);
+        saved.carrying.iter_mut().for_each(strip_type_path);
+    }
+    Some(quick_save)
+}
+
+fn copy_profile_quick_save(mut reader: EventReader<ProfileCopied>, mut pkv: ResMut<PkvStore>) {
+    for ProfileCopied { from, to } in reader.iter() {
+        let quick_save = some_or!(pkv.get::<Option<QuickSave>>(&profile_pkv_key(*from, QUICK_SAVE_PKV_KEY)).ok(); continue);
+        if let Err(err) = pkv.set(&profile_pkv_key(*to, QUICK_SAVE_PKV_KEY), &quick_save) {
+            error!("Cannot copy quick-save: {}", err);
+        }
+    }
+}
+
+fn clear_deleted_profile_quick_save(
+    mut reader: EventReader<ProfileDeleted>,
+    mut pkv: ResMut<PkvStore>,
+) {
+    for ProfileDeleted { id } in reader.iter() {
+        let key = profile_pkv_key(*id, QUICK_SAVE_PKV_KEY);
+        if let Err(err) = pkv.set(&key, &Option::<QuickSave>::None) {
+            error!("Cannot clear quick-save: {}", err);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_quick_save_request(
+    mut reader: EventReader<QuickSaveRequest>,
+    level_progress: Res<LevelProgress>,
+    active_profile: Res<ActiveProfile>,
+    current_run_stats: Res<CurrentRunStats>,
+    origin_query: Query<(Entity, &YoleckManaged, &LevelEntityOrigin)>,
+    world_snapshotter: WorldSnapshotter,
+    mut pkv: ResMut<PkvStore>,
+    mut quick_save_slot: ResMut<QuickSaveSlot>,
+) {
+    if reader.iter().next().is_none() {
+        return;
+    }
+    let level = some_or!(level_progress.current_level.clone(); {
+        error!("Cannot quick-save when not in a level");
+        return;
+    });
+    let key_by_entity = unique_entity_keys(origin_query.iter());
+    let snapshot = world_snapshotter.take();
+    let quick_save = QuickSave {
+        level,
+        entities: snapshot
+            .entities
+            .iter()
+            .filter_map(|(entity, entity_snapshot)| {
+                Some(SavedEntity {
+                    key: key_by_entity.get(entity)?.clone(),
+                    translation: entity_snapshot.translation,
+                    velocity: entity_snapshot
+                        .velocity
+                        .map(|velocity| (velocity.linvel, velocity.angvel)),
+                    hdirection: entity_snapshot.hdirection,
+                    carried_by: entity_snapshot
+                        .carried_by
+                        .and_then(|entity| key_by_entity.get(&entity).cloned()),
+                    carrying: entity_snapshot
+                        .carrying
+                        .and_then(|entity| key_by_entity.get(&entity).cloned()),
+                    breakable_life: entity_snapshot.breakable_life,
+                    retired: entity_snapshot.retired,
+                })
+            })
+            .collect(),
+        stats: current_run_stats.stats.clone(),
+    };
+    let key = some_or!(active_profile.pkv_key(QUICK_SAVE_PKV_KEY); {
+        error!("Cannot quick-save - no profile is active");
+        return;
+    });
+    if let Err(err) = pkv.set(&key, &Some(&quick_save)) {
+        error!("Cannot quick-save: {}", err);
+        return;
+    }
+    quick_save_slot.saved = Some(quick_save);
+}
+
+fn handle_quick_load_request(
+    mut reader: EventReader<QuickLoadRequest>,
+    mut quick_save_slot: ResMut<QuickSaveSlot>,
+    mut level_progress: ResMut<LevelProgress>,
+    mut state: ResMut<State<AppState>>,
+) {
+    if reader.iter().next().is_none() {
+        return;
+    }
+    let level = some_or!(quick_save_slot.saved_level(); {
+        error!("Cannot resume - there is no quick-save");
+        return;
+    });
+    level_progress.current_level = Some(level.to_owned());
+    quick_save_slot.pending_load = true;
+    state.set(AppState::LoadLevel).unwrap();
+}
+
+fn apply_pending_quick_load(
+    mut quick_save_slot: ResMut<QuickSaveSlot>,
+    mut current_run_stats: ResMut<CurrentRunStats>,
+    origin_query: Query<(Entity, &YoleckManaged, &LevelEntityOrigin)>,
+    mut world_snapshotter: WorldSnapshotter,
+) {
+    if !quick_save_slot.pending_load {
+        return;
+    }
+    if origin_query.is_empty() {
+        // Level is not populated yet
+        return;
+    }
+    quick_save_slot.pending_load = false;
+    let quick_save = some_or!(quick_save_slot.saved.as_ref(); return);
+    let entity_by_key: HashMap<SavedEntityKey, Entity> = unique_entity_keys(origin_query.iter())
+        .into_iter()
+        .map(|(entity, key)| (key, entity))
+        .collect();
+    let resolve = |key: &Option<SavedEntityKey>| entity_by_key.get(key.as_ref()?).copied();
+    let snapshot = WorldSnapshot {
+        entities: quick_save
+            .entities
+            .iter()
+            .filter_map(|saved| {
+                let entity = *entity_by_key.get(&saved.key)?;
+                Some((
+                    entity,
+                    EntitySnapshot {
+                        translation: saved.translation,
+                        velocity: saved
+                            .velocity
+                            .map(|(linvel, angvel)| Velocity { linvel, angvel }),
+                        hdirection: saved.hdirection,
+                        carried_by: resolve(&saved.carried_by),
+                        carrying: resolve(&saved.carrying),
+                        breakable_life: saved.breakable_life,
+                        retired: saved.retired,
+                    },
+                ))
+            })
+            .collect(),
+    };
+    world_snapshotter.restore(&snapshot);
+    current_run_stats.stats = quick_save.stats.clone();
+}
+
+/// A quick-save of a level that was just completed would only offer to resume a finished level.
+fn clear_completed_quick_save(
+    level_progress: Res<LevelProgress>,
+    active_profile: Res<ActiveProfile>,
+    mut pkv: ResMut<PkvStore>,
+    mut quick_save_slot: ResMut<QuickSaveSlot>,
+) {
+    let completed_level = some_or!(level_progress.current_level.as_deref(); return);
+    if quick_save_slot.saved_level() != Some(completed_level) {
+        return;
+    }
+    quick_save_slot.saved = None;
+    let key = some_or!(active_profile.pkv_key(QUICK_SAVE_PKV_KEY); return);
+    if let Err(err) = pkv.set(&key, &Option::<QuickSave>::None) {
+        error!("Cannot clear quick-save: {}", err);
+    }
+}
@@ -0,0 +1,84 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::global_types::{AppState, InputBinding, IsPlayer};
+use crate::world_snapshot::{WorldSnapshot, WorldSnapshotter};
+
+pub struct RewindPlugin;
+
+impl Plugin for RewindPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(RewindSettings {
+            snapshot_interval: 0.1,
+            max_snapshots: 50,
+        });
+        app.init_resource::<RewindState>();
+        app.add_system_set(SystemSet::on_enter(AppState::LoadLevel).with_system(
+            |mut rewind_state: ResMut<RewindState>| {
+                *rewind_state = Default::default();
+            },
+        ));
+        app.add_system_set(SystemSet::on_update(AppState::Game).with_system(record_or_rewind));
+    }
+}
+
+pub struct RewindSettings {
+    /// Seconds between two snapshots, both when recording and when rewinding.
+    pub snapshot_interval: f32,
+    /// Together with `snapshot_interval`, determines how far back the player can rewind.
+    pub max_snapshots: usize,
+}
+
+#[derive(Default)]
+pub struct RewindState {
+    snapshots: VecDeque<WorldSnapshot>,
+    time_since_last_step: f32,
+    rewinding: bool,
+}
+
+impl RewindState {
+    pub fn is_rewinding(&self) -> bool {
+        self.rewinding
+    }
+}
+
+fn record_or_rewind(
+    time: Res<Time>,
+    rewind_settings: Res<RewindSettings>,
+    mut rewind_state: ResMut<RewindState>,
+    player_query: Query<&ActionState<InputBinding>, With<IsPlayer>>,
+    mut world_snapshotter: WorldSnapshotter,
+) {
+    let rewinding = player_query
+        .iter()
+        .any(|action_state| action_state.pressed(InputBinding::Rewind));
+    if rewinding != rewind_state.rewinding {
+        rewind_state.rewinding = rewinding;
+        // Start stepping back (or recording again) immediately.
+        rewind_state.time_since_last_step = rewind_settings.snapshot_interval;
+    } else {
+        rewind_state.time_since_last_step += time.delta_seconds();
+    }
+    if rewind_state.time_since_last_step < rewind_settings.snapshot_interval {
+        return;
+    }
+    rewind_state.time_since_last_step = 0.0;
+
+    if rewinding {
+        // Keep the oldest snapshot, so that holding the button longer stays there instead of
+        // resuming the simulation.
+        if 1 < rewind_state.snapshots.len() {
+            rewind_state.snapshots.pop_back();
+        }
+        if let Some(snapshot) = rewind_state.snapshots.back() {
+            world_snapshotter.restore(snapshot);
+        }
+    } else {
+        rewind_state.snapshots.push_back(world_snapshotter.take());
+        while rewind_settings.max_snapshots < rewind_state.snapshots.len() {
+            rewind_state.snapshots.pop_front();
+        }
+    }
+}
@@ -4,9 +4,11 @@ use bevy_rapier2d::prelude::*;
 use bevy_yoleck::{egui, YoleckEdit, YoleckExtForApp, YoleckPopulate, YoleckTypeHandler};
 use serde::{Deserialize, Serialize};
 
+use crate::animation::{AnimationState, SpriteAnimation};
 use crate::global_types::{
     Activatable, Carrier, HDirection, HalfHeight, IsMountBase, IsPowerSource, Pickable,
 };
+use crate::laser::Breakable;
 use crate::loading::GameAssets;
 use crate::part_behavior::{HoverBehavior, LaserBehavior, RotatorBehavior};
 
@@ -34,6 +36,8 @@ pub struct RobotPart {
     part_type: RobotPartType,
     #[serde(default = "default_direction")]
     hdirection: HDirection,
+    #[serde(default)]
+    fragile: bool,
 }
 
 fn default_type() -> RobotPartType {
@@ -73,9 +77,14 @@ fn populate(mut populate: YoleckPopulate<RobotPart>, game_assets: Res<GameAssets
         cmd.insert(LockedAxes::ROTATION_LOCKED);
 
         cmd.insert(data.hdirection);
+        cmd.insert(data.part_type);
+        cmd.insert(SpriteAnimation::new(AnimationState::Idle));
 
         if !ctx.is_in_editor() {
             data.part_type.fill_components(&mut cmd);
+            if data.fragile {
+                cmd.insert(Breakable::default());
+            }
             cmd.with_children(|commands| {
                 let mut cmd = commands.spawn();
                 cmd.insert_bundle(SpatialBundle {
@@ -106,10 +115,11 @@ fn edit(mut edit: YoleckEdit<RobotPart>, mut _commands: Commands) {
                     );
                 }
             });
+        ui.checkbox(&mut data.fragile, "Fragile?");
     });
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RobotPartType {
     Platform,
     Hover,
@@ -0,0 +1,260 @@
+use bevy::prelude::*;
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::window::WindowMode;
+use bevy_egui::EguiSettings;
+use bevy_pkv::PkvStore;
+use leafwing_input_manager::prelude::InputMap;
+use serde::{Deserialize, Serialize};
+
+use crate::controls::ControlBindings;
+use crate::global_types::InputBinding;
+use crate::profile::{profile_pkv_key, ActiveProfile, ProfileCopied, ProfileDeleted};
+use crate::utils::some_or;
+
+pub struct SettingsPlugin {
+    pub is_editor: bool,
+}
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameSettings>();
+        if self.is_editor {
+            return;
+        }
+        app.add_system(read_settings);
+        app.add_system(apply_settings.after(read_settings));
+        app.add_system(copy_profile_settings);
+        app.add_system(clear_deleted_profile_settings);
+    }
+}
+
+const SETTINGS_PKV_KEY: &str = "settings";
+
+pub const RESOLUTIONS: &[[u32; 2]] = &[
+    [800, 600],
+    [1024, 768],
+    [1280, 720],
+    [1600, 900],
+    [1920, 1080],
+];
+pub const UI_SCALES: &[f64] = &[1.0, 1.5, 2.0, 2.5, 3.0];
+pub const STICK_DEADZONES: &[f32] = &[0.05, 0.1, 0.15, 0.2, 0.3];
+pub const VOLUMES: &[f32] = &[0.0, 0.25, 0.5, 0.75, 1.0];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum WindowModeSetting {
+    Windowed,
+    BorderlessFullscreen,
+    Fullscreen,
+}
+
+impl WindowModeSetting {
+    pub const ALL: &'static [WindowModeSetting] = &[
+        WindowModeSetting::Windowed,
+        WindowModeSetting::BorderlessFullscreen,
+        WindowModeSetting::Fullscreen,
+    ];
+
+    pub fn caption(&self) -> &'static str {
+        match self {
+            WindowModeSetting::Windowed => "Windowed",
+            WindowModeSetting::BorderlessFullscreen => "Borderless",
+            WindowModeSetting::Fullscreen => "Fullscreen",
+        }
+    }
+}
+
+/// Saved per profile. Missing fields (e.g. from older saves) get their default values.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameSettings {
+    pub window_mode: WindowModeSetting,
+    pub resolution: [u32; 2],
+    pub ui_scale: f64,
+    pub stick_deadzone: f32,
+    /// Accessibility option - jump by pushing up, in addition to the jump binding.
+    pub up_to_jump: bool,
+    pub controls: ControlBindings,
+    pub second_player_controls: ControlBindings,
+    /// Levels may have a second player, which only plays in co-op.
+    pub num_players: usize,
+    pub master_volume: f32,
+    pub sfx_volume: f32,
+    pub music_volume: f32,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            window_mode: WindowModeSetting::Windowed,
+            resolution: [800, 600],
+            ui_scale: 2.0,
+            stick_deadzone: 0.1,
+            up_to_jump: true,
+            controls: Default::default(),
+            second_player_controls: Default::default(),
+            num_players: 1,
+            master_volume: 1.0,
+            sfx_volume: 1.0,
+            music_volume: 0.5,
+        }
+    }
+}
+
+pub const MAX_PLAYERS: usize = 2;
+
+impl GameSettings {
+    pub fn player_controls(&self, player_index: usize) -> &ControlBindings {
+        if player_index == 0 {
+            &self.controls
+        } else {
+            &self.second_player_controls
+        }
+    }
+
+    pub fn player_controls_mut(&mut self, player_index: usize) -> &mut ControlBindings {
+        if player_index == 0 {
+            &mut self.controls
+        } else {
+            &mut self.second_player_controls
+        }
+    }
+
+    pub fn effective_sfx_volume(&self) -> f32 {
+        self.master_volume * self.sfx_volume
+    }
+
+    pub fn effective_music_volume(&self) -> f32 {
+        self.master_volume * self.music_volume
+    }
+
+    pub fn is_player_active(&self, player_index: usize) -> bool {
+        player_index < self.num_players
+    }
+
+    /// In co-op, each player gets their own gamepad, and the keyboard is split by having each
+    /// player give up the keys that later players are bound to.
+    pub fn build_input_map(&self, player_index: usize) -> InputMap<InputBinding> {
+        let excluded_keys: Vec<KeyCode> = (player_index + 1..self.num_players)
+            .flat_map(|other_player_index| {
+                self.player_controls(other_player_index)
+                    .keys(other_player_index)
+            })
+            .collect();
+        let mut input_map = self.player_controls(player_index).build_input_map(
+            player_index,
+            self.stick_deadzone,
+            &excluded_keys,
+        );
+        if 1 < self.num_players {
+            input_map.set_gamepad(Gamepad { id: player_index });
+        }
+        input_map
+    }
+
+    pub fn save(&self, pkv: &mut PkvStore, active_profile: &ActiveProfile) {
+        let key = some_or!(active_profile.pkv_key(SETTINGS_PKV_KEY); {
+            error!("Cannot save settings - no profile is active");
+            return;
+        });
+        if let Err(err) = pkv.set(&key, self) {
+            error!("Cannot save settings: {}", err);
+        }
+    }
+}
+
+/// Picks the option after `current`, wrapping around. Used for cycling settings with a single
+/// button, which is easier to navigate with a gamepad than combo boxes.
+pub fn next_option<T: Copy + PartialEq>(options: &[T], current: T) -> T {
+    let index = options
+        .iter()
+        .position(|option| *option == current)
+        .map(|index| index + 1)
+        .unwrap_or(0);
+    options[index % options.len()]
+}
+
+fn read_settings(
+    pkv: Res<PkvStore>,
+    active_profile: Res<ActiveProfile>,
+    mut settings: ResMut<GameSettings>,
+) {
+    if !active_profile.is_changed() {
+        return;
+    }
+    *settings = active_profile
+        .pkv_key(SETTINGS_PKV_KEY)
+        .and_then(|key| pkv.get::<GameSettings>(&key).ok())
+        .unwrap_or_default();
+}
+
+/// The window is only touched when its own settings differ from what was last applied (or from how
+/// the window was launched), so that e.g. changing the volume does not undo a manual resize.
+fn apply_settings(
+    settings: Res<GameSettings>,
+    #[cfg(not(target_arch = "wasm32"))] window_descriptor: Res<WindowDescriptor>,
+    #[cfg(not(target_arch = "wasm32"))] mut windows: ResMut<Windows>,
+    mut egui_settings: ResMut<EguiSettings>,
+    #[cfg(not(target_arch = "wasm32"))] mut applied_window_settings: Local<
+        Option<(WindowModeSetting, [u32; 2])>,
+    >,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    if egui_settings.scale_factor != settings.ui_scale {
+        egui_settings.scale_factor = settings.ui_scale;
+    }
+    // The browser controls the canvas size, and the menu does not offer these options there.
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let window_settings = (settings.window_mode, settings.resolution);
+        let applied = applied_window_settings.get_or_insert_with(|| {
+            let window_mode = match window_descriptor.mode {
+                WindowMode::Windowed => WindowModeSetting::Windowed,
+                WindowMode::BorderlessFullscreen => WindowModeSetting::BorderlessFullscreen,
+                WindowMode::SizedFullscreen | WindowMode::Fullscreen => {
+                    WindowModeSetting::Fullscreen
+                }
+            };
+            let resolution = [
+                window_descriptor.width as u32,
+                window_descriptor.height as u32,
+            ];
+            (window_mode, resolution)
+        });
+        if *applied == window_settings {
+            return;
+        }
+        *applied = window_settings;
+        let window = some_or!(windows.get_primary_mut(); return);
+        window.set_mode(match settings.window_mode {
+            WindowModeSetting::Windowed => WindowMode::Windowed,
+            WindowModeSetting::BorderlessFullscreen => WindowMode::BorderlessFullscreen,
+            WindowModeSetting::Fullscreen => WindowMode::Fullscreen,
+        });
+        let [width, height] = settings.resolution;
+        window.set_resolution(width as f32, height as f32);
+    }
+}
+
+fn copy_profile_settings(mut reader: EventReader<ProfileCopied>, mut pkv: ResMut<PkvStore>) {
+    for ProfileCopied { from, to } in reader.iter() {
+        let settings = some_or!(pkv.get::<GameSettings>(&profile_pkv_key(*from, SETTINGS_PKV_KEY)).ok(); continue);
+        if let Err(err) = pkv.set(&profile_pkv_key(*to, SETTINGS_PKV_KEY), &settings) {
+            error!("Cannot copy settings: {}", err);
+        }
+    }
+}
+
+fn clear_deleted_profile_settings(
+    mut reader: EventReader<ProfileDeleted>,
+    mut pkv: ResMut<PkvStore>,
+) {
+    for ProfileDeleted { id } in reader.iter() {
+        let key = profile_pkv_key(*id, SETTINGS_PKV_KEY);
+        if let Err(err) = pkv.set(&key, &GameSettings::default()) {
+            error!("Cannot clear settings: {}", err);
+        }
+    }
+}
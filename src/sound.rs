@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+
+use crate::door::DoorOpened;
+use crate::door_key::DoorKeyCollected;
+use crate::global_types::AppState;
+use crate::laser::{LaserHit, TriggerLaserShot};
+use crate::level_settings::{LevelMusic, DEFAULT_MUSIC};
+use crate::loading::GameAssets;
+use crate::parts_manipulation::PartMounted;
+use crate::player_control::PlayerAction;
+use crate::settings::GameSettings;
+
+pub struct SoundPlugin {
+    pub is_editor: bool,
+}
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SoundEffect>();
+        app.add_system(gameplay_sound_effects);
+        app.add_system_set(SystemSet::on_enter(AppState::LevelCompleted).with_system(
+            |mut writer: EventWriter<SoundEffect>| {
+                writer.send(SoundEffect::LevelComplete);
+            },
+        ));
+        app.add_system(play_sound_effects.after(gameplay_sound_effects));
+        if !self.is_editor {
+            app.init_resource::<MusicPlayback>();
+            app.add_system(play_music);
+        }
+    }
+}
+
+/// Send this event to play a sound effect at the effects volume.
+#[derive(Clone, Copy, Debug)]
+pub enum SoundEffect {
+    Jump,
+    Pickup,
+    Drop,
+    Mount,
+    LaserFire,
+    WallHit,
+    WallBreak,
+    KeyCollected,
+    DoorOpen,
+    LevelComplete,
+}
+
+impl SoundEffect {
+    fn source(&self, game_assets: &GameAssets) -> Handle<AudioSource> {
+        match self {
+            SoundEffect::Jump => game_assets.jump_sound.clone(),
+            SoundEffect::Pickup => game_assets.pickup_sound.clone(),
+            SoundEffect::Drop => game_assets.drop_sound.clone(),
+            SoundEffect::Mount => game_assets.mount_sound.clone(),
+            SoundEffect::LaserFire => game_assets.laser_fire_sound.clone(),
+            SoundEffect::WallHit => game_assets.wall_hit_sound.clone(),
+            SoundEffect::WallBreak => game_assets.wall_break_sound.clone(),
+            SoundEffect::KeyCollected => game_assets.key_collected_sound.clone(),
+            SoundEffect::DoorOpen => game_assets.door_open_sound.clone(),
+            SoundEffect::LevelComplete => game_assets.level_complete_sound.clone(),
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn gameplay_sound_effects(
+    mut player_action_reader: EventReader<PlayerAction>,
+    mut part_mounted_reader: EventReader<PartMounted>,
+    mut laser_shot_reader: EventReader<TriggerLaserShot>,
+    mut laser_hit_reader: EventReader<LaserHit>,
+    mut door_key_collected_reader: EventReader<DoorKeyCollected>,
+    mut door_opened_reader: EventReader<DoorOpened>,
+    mut writer: EventWriter<SoundEffect>,
+) {
+    for player_action in player_action_reader.iter() {
+        writer.send(match player_action {
+            PlayerAction::Jump => SoundEffect::Jump,
+            PlayerAction::Pickup => SoundEffect::Pickup,
+            PlayerAction::Throw => SoundEffect::Drop,
+        });
+    }
+    for _ in part_mounted_reader.iter() {
+        writer.send(SoundEffect::Mount);
+    }
+    // Several lasers often fire together - one sound is enough
+    if 0 < laser_shot_reader.iter().count() {
+        writer.send(SoundEffect::LaserFire);
+    }
+    for laser_hit in laser_hit_reader.iter() {
+        writer.send(if laser_hit.destroyed {
+            SoundEffect::WallBreak
+        } else {
+            SoundEffect::WallHit
+        });
+    }
+    for _ in door_key_collected_reader.iter() {
+        writer.send(SoundEffect::KeyCollected);
+    }
+    for _ in door_opened_reader.iter() {
+        writer.send(SoundEffect::DoorOpen);
+    }
+}
+
+fn play_sound_effects(
+    mut reader: EventReader<SoundEffect>,
+    audio: Res<Audio>,
+    game_assets: Res<GameAssets>,
+    settings: Res<GameSettings>,
+) {
+    let volume = settings.effective_sfx_volume();
+    for sound_effect in reader.iter() {
+        if volume <= 0.0 {
+            continue;
+        }
+        audio.play_with_settings(
+            sound_effect.source(&game_assets),
+            PlaybackSettings::ONCE.with_volume(volume),
+        );
+    }
+}
+
+#[derive(Default)]
+struct MusicPlayback {
+    track: Option<String>,
+    sink: Option<Handle<AudioSink>>,
+}
+
+/// Switches the music when a level with a different track is loaded, and follows the volume
+/// settings.
+fn play_music(
+    level_music_query: Query<&LevelMusic>,
+    mut music_playback: ResMut<MusicPlayback>,
+    audio: Res<Audio>,
+    audio_sinks: Res<Assets<AudioSink>>,
+    asset_server: Res<AssetServer>,
+    settings: Res<GameSettings>,
+) {
+    let track = level_music_query
+        .get_single()
+        .map(|level_music| level_music.0.as_str())
+        .unwrap_or(DEFAULT_MUSIC);
+    let volume = settings.effective_music_volume();
+    if music_playback.track.as_deref() == Some(track) {
+        if settings.is_changed() {
+            if let Some(sink) = music_playback
+                .sink
+                .as_ref()
+                .and_then(|sink| audio_sinks.get(sink))
+            {
+                sink.set_volume(volume);
+            }
+        }
+        return;
+    }
+    if let Some(sink) = music_playback
+        .sink
+        .take()
+        .and_then(|sink| audio_sinks.get(&sink))
+    {
+        sink.stop();
+    }
+    music_playback.track = Some(track.to_owned());
+    if track.is_empty() {
+        return;
+    }
+    let sink = audio.play_with_settings(
+        asset_server.load(format!("music/{}", track).as_str()),
+        PlaybackSettings::LOOP.with_volume(volume),
+    );
+    music_playback.sink = Some(audio_sinks.get_handle(sink));
+}
@@ -0,0 +1,229 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_rapier2d::prelude::*;
+use bevy_yoleck::YoleckManaged;
+
+use crate::global_types::{Carrier, HDirection, HalfHeight, Pickable};
+use crate::laser::{Breakable, Laser};
+use crate::player_control::Stunned;
+use crate::utils::some_or;
+
+pub struct WorldSnapshotPlugin;
+
+impl Plugin for WorldSnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(apply_retire);
+        app.add_system(restore_carry_joints);
+    }
+}
+
+/// Command-like component for removing a level entity from play (a collected key, a broken wall)
+/// without despawning it, so that a snapshot taken before that can bring it back.
+#[derive(Component)]
+pub struct Retire;
+
+#[derive(Component)]
+pub struct Retired {
+    rigid_body: Option<RigidBody>,
+    colliders: Vec<(Entity, Collider)>,
+}
+
+fn apply_retire(
+    query: Query<
+        (
+            Entity,
+            Option<&RigidBody>,
+            Option<&Collider>,
+            Option<&Children>,
+        ),
+        With<Retire>,
+    >,
+    child_collider_query: Query<&Collider>,
+    mut commands: Commands,
+) {
+    for (entity, rigid_body, collider, children) in query.iter() {
+        let mut colliders = Vec::new();
+        if let Some(collider) = collider {
+            colliders.push((entity, collider.clone()));
+        }
+        if let Some(children) = children {
+            for child in children.iter() {
+                if let Ok(collider) = child_collider_query.get(*child) {
+                    colliders.push((*child, collider.clone()));
+                    commands.entity(*child).remove::<Collider>();
+                }
+            }
+        }
+        commands
+            .entity(entity)
+            .remove::<Retire>()
+            .remove::<RigidBody>()
+            .remove::<Collider>()
+            .remove::<ImpulseJoint>()
+            .insert(Retired {
+                rigid_body: rigid_body.copied(),
+                colliders,
+            })
+            .insert(Visibility { is_visible: false });
+    }
+}
+
+fn unretire(commands: &mut Commands, entity: Entity, retired: &Retired) {
+    let mut cmd = commands.entity(entity);
+    cmd.remove::<Retired>();
+    cmd.insert(Visibility { is_visible: true });
+    if let Some(rigid_body) = retired.rigid_body {
+        cmd.insert(rigid_body);
+    }
+    for (collider_entity, collider) in retired.colliders.iter() {
+        commands.entity(*collider_entity).insert(collider.clone());
+    }
+}
+
+#[derive(Clone)]
+pub struct EntitySnapshot {
+    pub translation: Vec3,
+    pub velocity: Option<Velocity>,
+    pub hdirection: Option<HDirection>,
+    pub carried_by: Option<Entity>,
+    pub carrying: Option<Entity>,
+    pub breakable_life: Option<f32>,
+    pub retired: bool,
+}
+
+#[derive(Clone, Default)]
+pub struct WorldSnapshot {
+    pub entities: HashMap<Entity, EntitySnapshot>,
+}
+
+/// Placed on a carried part when restoring a snapshot changed who carries it. The joint cannot be
+/// replaced on the same frame the old one gets removed.
+#[derive(Component)]
+struct RestoreCarryJoint {
+    carrier_entity: Entity,
+}
+
+#[allow(clippy::type_complexity)]
+#[derive(SystemParam)]
+pub struct WorldSnapshotter<'w, 's> {
+    query: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static mut Transform,
+            Option<&'static mut Velocity>,
+            Option<&'static mut HDirection>,
+            Option<&'static mut Pickable>,
+            Option<&'static mut Carrier>,
+            Option<&'static mut Breakable>,
+            Option<&'static Retired>,
+        ),
+        With<YoleckManaged>,
+    >,
+    laser_query: Query<'w, 's, Entity, With<Laser>>,
+    commands: Commands<'w, 's>,
+}
+
+impl WorldSnapshotter<'_, '_> {
+    pub fn take(&self) -> WorldSnapshot {
+        WorldSnapshot {
+            entities: self
+                .query
+                .iter()
+                .map(
+                    |(
+                        entity,
+                        transform,
+                        velocity,
+                        hdirection,
+                        pickable,
+                        carrier,
+                        breakable,
+                        retired,
+                    )| {
+                        (
+                            entity,
+                            EntitySnapshot {
+                                translation: transform.translation,
+                                velocity: velocity.copied(),
+                                hdirection: hdirection.copied(),
+                                carried_by: pickable.and_then(|pickable| pickable.carried_by),
+                                carrying: carrier.and_then(|carrier| carrier.carrying),
+                                breakable_life: breakable.map(|breakable| breakable.life),
+                                retired: retired.is_some(),
+                            },
+                        )
+                    },
+                )
+                .collect(),
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &WorldSnapshot) {
+        for laser_entity in self.laser_query.iter() {
+            self.commands.entity(laser_entity).despawn_recursive();
+        }
+        for (entity, mut transform, velocity, hdirection, pickable, carrier, breakable, retired) in
+            self.query.iter_mut()
+        {
+            let entity_snapshot = some_or!(snapshot.entities.get(&entity); continue);
+            transform.translation = entity_snapshot.translation;
+            if let (Some(mut velocity), Some(snapshot_velocity)) =
+                (velocity, entity_snapshot.velocity)
+            {
+                *velocity = snapshot_velocity;
+            }
+            if let (Some(mut hdirection), Some(snapshot_hdirection)) =
+                (hdirection, entity_snapshot.hdirection)
+            {
+                *hdirection = snapshot_hdirection;
+            }
+            if let Some(mut pickable) = pickable {
+                // Retiring removes the joint, so it needs to be recreated even if the carrier
+                // stays the same.
+                let is_unretiring = retired.is_some() && !entity_snapshot.retired;
+                if pickable.carried_by != entity_snapshot.carried_by || is_unretiring {
+                    pickable.carried_by = entity_snapshot.carried_by;
+                    let mut cmd = self.commands.entity(entity);
+                    cmd.remove::<ImpulseJoint>();
+                    if let Some(carrier_entity) = entity_snapshot.carried_by {
+                        cmd.insert(RestoreCarryJoint { carrier_entity });
+                    }
+                }
+            }
+            if let Some(mut carrier) = carrier {
+                carrier.carrying = entity_snapshot.carrying;
+            }
+            if let (Some(mut breakable), Some(life)) = (breakable, entity_snapshot.breakable_life) {
+                breakable.life = life;
+            }
+            match (retired, entity_snapshot.retired) {
+                (None, true) => {
+                    self.commands.entity(entity).insert(Retire);
+                }
+                (Some(retired), false) => {
+                    unretire(&mut self.commands, entity, retired);
+                }
+                _ => {}
+            }
+            self.commands.entity(entity).remove::<Stunned>();
+        }
+    }
+}
+
+fn restore_carry_joints(
+    query: Query<(Entity, &RestoreCarryJoint, &HalfHeight)>,
+    carrier_query: Query<&HalfHeight>,
+    mut commands: Commands,
+) {
+    for (entity, &RestoreCarryJoint { carrier_entity }, HalfHeight(pickable_hh)) in query.iter() {
+        let mut cmd = commands.entity(entity);
+        cmd.remove::<RestoreCarryJoint>();
+        let HalfHeight(carrier_hh) = some_or!(carrier_query.get(carrier_entity).ok(); continue);
+        let joint =
+            FixedJointBuilder::new().local_anchor1(Vec2::new(0.0, 0.01 + carrier_hh + pickable_hh));
+        cmd.insert(ImpulseJoint::new(carrier_entity, joint));
+    }
+}
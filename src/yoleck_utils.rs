@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 use bevy_egui::egui;
-use bevy_yoleck::{YoleckEdit, YoleckPopulate, YoleckTypeHandler};
+use bevy_yoleck::{YoleckEdit, YoleckEditContext, YoleckPopulate, YoleckTypeHandler};
 
 pub const GRANULARITY: f32 = 1.0;
 
@@ -12,6 +12,14 @@ pub fn round_vec2_to_tick(vec: Vec2, tick: f32) -> Vec2 {
     Vec2::new(round_to_tick(vec.x, tick), round_to_tick(vec.y, tick))
 }
 
+/// Where a level entity was placed in the level file. Together with its Yoleck type name, this
+/// identifies the entity so that state saved in one session can be matched with the entities when
+/// the level is loaded again.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct LevelEntityOrigin {
+    pub position: IVec2,
+}
+
 pub fn position_adapter<T: 'static>(
     projection: impl 'static + Clone + Send + Sync + Fn(&mut T) -> (&mut Vec2, IVec2),
     z: f32,
@@ -25,6 +33,9 @@ pub fn position_adapter<T: 'static>(
                         let (position, size) = projection(data);
                         let center_position = *position + (0.5 * GRANULARITY) * size.as_vec2();
                         cmd.insert(Transform::from_translation(center_position.extend(z)));
+                        cmd.insert(LevelEntityOrigin {
+                            position: position.round().as_ivec2(),
+                        });
                     });
                 }
             })
@@ -53,3 +64,53 @@ pub fn position_adapter<T: 'static>(
             })
     }
 }
+
+pub fn edit_size(
+    ctx: &YoleckEditContext,
+    commands: &mut Commands,
+    ui: &mut egui::Ui,
+    position: &mut Vec2,
+    size: &mut IVec2,
+) {
+    for move_anchor in [(false, false), (false, true), (true, false), (true, true)] {
+        let mut resize_knob = ctx.knob(commands, ("resize", move_anchor));
+        let anchor_offset = IVec2::new(move_anchor.0 as i32, move_anchor.1 as i32).as_vec2();
+        let anchor_position = *position + (anchor_offset * size.as_vec2()) * GRANULARITY;
+        let knob_position =
+            *position + ((Vec2::ONE - anchor_offset) * size.as_vec2()) * GRANULARITY;
+        resize_knob.cmd.insert_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: Color::ORANGE_RED,
+                custom_size: Some(Vec2::new(0.3, 0.3)),
+                ..Default::default()
+            },
+            transform: Transform::from_translation(knob_position.extend(1.0)),
+            global_transform: Transform::from_translation(knob_position.extend(1.0)).into(),
+            ..Default::default()
+        });
+        if let Some(new_knob_pos) = resize_knob.get_passed_data::<Vec2>() {
+            let new_size = *new_knob_pos - anchor_position;
+
+            for (is_anchor, new_dim, dim, coord) in [
+                (move_anchor.0, new_size.x, &mut size.x, &mut position.x),
+                (move_anchor.1, new_size.y, &mut size.y, &mut position.y),
+            ] {
+                let mut new_dim = new_dim.round();
+                if is_anchor {
+                    new_dim = -new_dim;
+                }
+                let new_dim = f32::max(new_dim, GRANULARITY) as i32;
+                if is_anchor && new_dim != *dim {
+                    *coord += (*dim - new_dim) as f32;
+                }
+                *dim = new_dim;
+            }
+        }
+    }
+
+    ui.horizontal(|ui| {
+        for (caption, value) in [("Width:", &mut size.x), ("Height:", &mut size.y)] {
+            ui.add(egui::DragValue::new(value).prefix(caption).speed(0.05));
+        }
+    });
+}